@@ -0,0 +1,35 @@
+use std::fs;
+
+use clap::{Parser, Subcommand};
+use eyre::Result;
+use tracing::info;
+
+use crate::App;
+
+/// Manage the cache of exported prototypes
+#[derive(Debug, Parser)]
+pub struct CacheCommand {
+    #[command(subcommand)]
+    command: CacheSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheSubcommand {
+    /// Remove all cached exports
+    Clear,
+}
+
+impl CacheCommand {
+    pub async fn execute(&self, app: &App) -> Result<()> {
+        match self.command {
+            CacheSubcommand::Clear => {
+                let dir = app.export_cache_dir();
+                if dir.exists() {
+                    info!("removing {}", dir.display());
+                    fs::remove_dir_all(dir)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
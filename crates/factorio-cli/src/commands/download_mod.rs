@@ -1,36 +1,106 @@
-use std::{env, fs::File, io::BufReader};
+use std::{env, path::PathBuf};
 
 use clap::Parser;
-use eyre::bail;
-use factorio_mod_api::{api::ApiToken, ModPortalClient};
+use factorio_mod_api::api::{ModDependency, ModRelease};
+use futures::{stream, StreamExt, TryStreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use semver::Version;
+use tracing::info;
 
 use crate::App;
 
+/// Maximum number of mods that are downloaded at the same time.
+const MAX_PARALLEL_DOWNLOADS: usize = 4;
+
 /// Download a mod from the mod portal.
 #[derive(Debug, Parser)]
 pub struct DownloadModCommand {
     /// Name of the mod
     mod_name: String,
 
-    /// Version of the mod
-    mod_version: Version,
+    /// Version of the mod. Defaults to the latest release that is compatible
+    /// with the targeted Factorio version.
+    mod_version: Option<Version>,
+
+    /// Also download all dependencies of the mod, resolved like with `fct
+    /// resolve-mods`
+    #[arg(long)]
+    with_deps: bool,
+
+    /// Directory to download the mods into. Defaults to the current directory.
+    /// Files that already exist with the correct hash are skipped.
+    #[arg(long)]
+    dir: Option<PathBuf>,
+
+    /// Download mods for this version of Factorio, e.g. "1.1" or "2.0.28".
+    /// Defaults to the version of the configured Factorio binary, if any.
+    #[arg(long)]
+    factorio_version: Option<String>,
 }
 
 impl DownloadModCommand {
     pub async fn execute(&self, app: &App) -> eyre::Result<()> {
-        let token_file = app.api_token_path();
-        if !token_file.exists() {
-            bail!("API token not found. Please use `fct login` first.");
-        }
+        let token = app.api_token()?;
+        let client = app.mod_portal_client(false)?;
+        let options = app.resolve_options(self.factorio_version.as_deref())?;
 
-        let token: ApiToken = serde_json::from_reader(BufReader::new(File::open(token_file)?))?;
+        let releases: Vec<ModRelease> = if self.with_deps {
+            let dependency = match &self.mod_version {
+                Some(version) => ModDependency::try_from(format!("{} = {version}", self.mod_name))?,
+                None => ModDependency::unversioned(self.mod_name.clone()),
+            };
+            let resolution = client.resolve_mods(&[dependency], &options).await?;
+            resolution.releases.into_values().collect()
+        } else {
+            let release = match &self.mod_version {
+                Some(version) => client.get_release(&self.mod_name, version).await?,
+                None => client.get_latest_release(&self.mod_name, options.factorio_version).await?,
+            };
+            vec![release.clone()]
+        };
 
-        let client = ModPortalClient::new()?;
-        client
-            .download_mod(&self.mod_name, &self.mod_version, &token, &env::current_dir()?)
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => env::current_dir()?,
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        let progress = MultiProgress::new();
+        stream::iter(&releases)
+            .map(|release| {
+                let (client, token, dir, progress) = (&client, &token, &dir, &progress);
+                async move {
+                    let bar = progress.add(download_progress_bar(&release.file_name));
+                    info!("downloading {}", release.file_name);
+                    client
+                        .download_release_with_progress(release, token, dir, |p| {
+                            if let Some(total) = p.total {
+                                bar.set_length(total);
+                            }
+                            bar.set_position(p.received);
+                        })
+                        .await?;
+                    bar.finish();
+                    eyre::Ok(())
+                }
+            })
+            .buffer_unordered(MAX_PARALLEL_DOWNLOADS)
+            .try_collect::<Vec<()>>()
             .await?;
 
         Ok(())
     }
 }
+
+/// A progress bar for downloading a file.
+pub fn download_progress_bar(file_name: &str) -> ProgressBar {
+    ProgressBar::new(0)
+        .with_style(
+            ProgressStyle::with_template(
+                "{msg} [{bar:40}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+            )
+            .expect("the progress bar template should be valid")
+            .progress_chars("=> "),
+        )
+        .with_message(file_name.to_owned())
+}
@@ -1,8 +1,13 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use clap::{Parser, ValueEnum};
-use eyre::Result;
-use factorio_exporter::{FactorioExporter, FactorioExporterError};
+use eyre::{eyre, Result};
+use factorio_exporter::{normalize::normalize_recipes, FactorioExporter, FactorioExporterError};
+use factorio_mod_api::{mod_settings::PropertyTree, resolver::BUILTIN_MODS};
 use indoc::printdoc;
 use serde_yaml::Value;
 use tracing::{debug, info};
@@ -27,7 +32,54 @@ pub struct ExportCommand {
     #[arg(long, short, default_value = "json")]
     format: OutputFormat,
 
-    /// Mods to install before exporting the prototypes
+    /// Convert all recipes into a canonical form: explicit ingredient and
+    /// product types and amounts, a list of results, and separate `normal`
+    /// and `expensive` variants with all defaults applied
+    #[arg(long)]
+    normalize: bool,
+
+    /// Add a `translated_name` and `translated_description` in this language,
+    /// e.g. `de`, to each prototype. This runs Factorio a second time.
+    #[arg(long)]
+    locale: Option<String>,
+
+    /// Also export the icons of all prototypes into this directory, as
+    /// `<type>/<name>.png` files. This requires the full Factorio binary, not
+    /// the headless one.
+    #[arg(long, value_name = "DIR")]
+    icons: Option<PathBuf>,
+
+    /// Always run Factorio, instead of reusing the result of an earlier export
+    /// with the same Factorio version, mods, settings and locale. Use `fct
+    /// cache clear` to remove all cached results.
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Override a startup setting, e.g. `--setting my-mod-setting=true`.
+    /// Values are parsed as booleans, integers, numbers or strings, in this
+    /// order. Values of double settings need a decimal point, e.g. `2.0`,
+    /// unless the setting is already stored as a double in `--mod-settings`.
+    /// Can be repeated.
+    #[arg(long = "setting", value_name = "NAME=VALUE", value_parser = parse_setting)]
+    settings: Vec<(String, PropertyTree)>,
+
+    /// Use the mod settings from this `mod-settings.dat` file. Settings given
+    /// with `--setting` take precedence.
+    #[arg(long, value_name = "FILE")]
+    mod_settings: Option<PathBuf>,
+
+    /// Disable a mod, e.g. one of the built-in mods `space-age`, `quality` or
+    /// `elevated-rails`. Can be repeated.
+    #[arg(long = "disable-mod", value_name = "NAME")]
+    disabled_mods: Vec<String>,
+
+    /// Disable all built-in mods except `base`, e.g. to export the game
+    /// without expansions. Mods given as arguments are still installed.
+    #[arg(long)]
+    only_base: bool,
+
+    /// Mods to install before exporting the prototypes: `.zip` files, unpacked
+    /// mod directories, or folders containing mods
     mods: Vec<PathBuf>,
 }
 
@@ -36,12 +88,37 @@ impl ExportCommand {
         debug!("Parsed arguments: {:?}", self);
 
         let binary = app.factorio_binary()?;
-        let exporter = FactorioExporter::new(&binary, "en")?;
+        let mut exporter = FactorioExporter::new(&binary, self.locale.as_deref().unwrap_or("en"))?;
 
+        if !self.no_cache {
+            exporter.set_cache_dir(app.export_cache_dir());
+        }
         exporter.install_mods(&self.mods)?;
+        if let Some(path) = &self.mod_settings {
+            exporter.load_mod_settings(path)?;
+        }
+        exporter.set_startup_settings(self.settings.iter().cloned());
+
+        let mut disabled: Vec<&str> = self.disabled_mods.iter().map(String::as_str).collect();
+        if self.only_base {
+            disabled.extend(BUILTIN_MODS.iter().map(|(name, _)| *name).filter(|n| *n != "base"));
+        }
+        exporter.set_mods_enabled(disabled.into_iter().map(|name| (name, false)));
 
-        match exporter.export() {
-            Ok(prototypes) => {
+        let export = || {
+            let mut prototypes = exporter.export()?;
+            if self.locale.is_some() {
+                exporter.export_locale()?.apply(&mut prototypes);
+            }
+            let icons = self.icons.as_ref().map(|_| exporter.export_icons()).transpose()?;
+            Ok((prototypes, icons))
+        };
+
+        match export() {
+            Ok((mut prototypes, icons)) => {
+                if self.normalize {
+                    normalize_recipes(&mut prototypes)?;
+                }
                 let parsed: Value = serde_json::from_value(prototypes)?;
 
                 let output = match self.format {
@@ -54,6 +131,10 @@ impl ExportCommand {
                     Some(path) => fs::write(path, output)?,
                     None => println!("{}", output),
                 }
+
+                if let (Some(icons), Some(dir)) = (icons, &self.icons) {
+                    copy_icons(icons, dir)?;
+                }
             }
 
             Err(FactorioExporterError::FactorioExecutionError { stdout, stderr }) => {
@@ -71,3 +152,51 @@ impl ExportCommand {
         Ok(())
     }
 }
+
+/// Copies the exported icons into `dir`.
+fn copy_icons(icons: BTreeMap<(String, String), PathBuf>, dir: &Path) -> Result<()> {
+    info!("copying {} icons to {}", icons.len(), dir.display());
+    for ((prototype_type, name), path) in icons {
+        let type_dir = dir.join(prototype_type);
+        fs::create_dir_all(&type_dir)?;
+        fs::copy(path, type_dir.join(format!("{name}.png")))?;
+    }
+    Ok(())
+}
+
+/// Parses a `NAME=VALUE` pair for `--setting`.
+fn parse_setting(arg: &str) -> Result<(String, PropertyTree)> {
+    let (name, value) =
+        arg.split_once('=').ok_or_else(|| eyre!("expected NAME=VALUE, got '{arg}'"))?;
+
+    let value = if let Ok(value) = value.parse() {
+        PropertyTree::Bool(value)
+    } else if let Ok(value) = value.parse() {
+        PropertyTree::SignedInteger(value)
+    } else if let Some(value) = value.parse().ok().filter(|value: &f64| value.is_finite()) {
+        PropertyTree::Number(value)
+    } else {
+        PropertyTree::String(value.into())
+    };
+    Ok((name.into(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use factorio_mod_api::mod_settings::PropertyTree;
+
+    use super::parse_setting;
+
+    #[test]
+    fn parse_setting_values() {
+        let parse = |arg| parse_setting(arg).unwrap().1;
+
+        assert_eq!(parse("name=true"), PropertyTree::Bool(true));
+        assert_eq!(parse("name=2"), PropertyTree::SignedInteger(2));
+        assert_eq!(parse("name=2.5"), PropertyTree::Number(2.5));
+        assert_eq!(parse("name=nan"), PropertyTree::String("nan".into()));
+        assert_eq!(parse("name=inf"), PropertyTree::String("inf".into()));
+        assert_eq!(parse("name=x=y"), PropertyTree::String("x=y".into()));
+        assert!(parse_setting("name").is_err());
+    }
+}
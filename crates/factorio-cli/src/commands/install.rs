@@ -0,0 +1,47 @@
+use std::{fs, path::PathBuf};
+
+use clap::Parser;
+use eyre::Result;
+use factorio_mod_api::lockfile::Lockfile;
+use tracing::info;
+
+use super::download_mod::download_progress_bar;
+use crate::App;
+
+/// Download exactly the mods recorded in a lockfile
+#[derive(Debug, Parser)]
+pub struct InstallCommand {
+    /// A lockfile written by `fct resolve-mods --lock`
+    #[arg(long, value_name = "LOCKFILE")]
+    locked: PathBuf,
+
+    /// Directory to download the mods into. Files that already exist with the
+    /// correct hash are skipped.
+    dir: PathBuf,
+}
+
+impl InstallCommand {
+    pub async fn execute(&self, app: &App) -> Result<()> {
+        let lockfile = Lockfile::read(&self.locked)?;
+        let token = app.api_token()?;
+        let client = app.mod_portal_client(false)?;
+
+        fs::create_dir_all(&self.dir)?;
+        for locked in &lockfile.mods {
+            info!("installing {} {}", locked.name, locked.version);
+
+            let progress = download_progress_bar(&locked.file_name);
+            client
+                .download_locked(locked, &token, &self.dir, |p| {
+                    if let Some(total) = p.total {
+                        progress.set_length(total);
+                    }
+                    progress.set_position(p.received);
+                })
+                .await?;
+            progress.finish();
+        }
+
+        Ok(())
+    }
+}
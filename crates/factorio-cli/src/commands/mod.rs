@@ -1,4 +1,9 @@
+pub mod cache;
 pub mod download_mod;
 pub mod export;
+pub mod install;
 pub mod login;
+pub mod mod_settings;
+pub mod mods;
+pub mod publish;
 pub mod resolve_mods;
@@ -0,0 +1,65 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read},
+    path::PathBuf,
+};
+
+use clap::{Parser, Subcommand};
+use eyre::Result;
+use factorio_mod_api::mod_settings::{ModSettings, MOD_SETTINGS};
+use tracing::info;
+
+use crate::App;
+
+/// Convert mod settings (`mod-settings.dat`) to and from JSON
+#[derive(Debug, Parser)]
+pub struct ModSettingsCommand {
+    /// The settings file. Defaults to `mod-settings.dat` in the mods directory
+    /// (see `fct mods --mods-dir`).
+    #[arg(long)]
+    file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: ModSettingsSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ModSettingsSubcommand {
+    /// Print the settings as JSON
+    Dump,
+
+    /// Replace the settings with the contents of a JSON file, in the format
+    /// printed by `dump`
+    Set {
+        /// The JSON file, or "-" to read from stdin
+        json: PathBuf,
+    },
+}
+
+impl ModSettingsCommand {
+    pub async fn execute(&self, app: &App) -> Result<()> {
+        let file = match &self.file {
+            Some(file) => file.clone(),
+            None => app.mods_dir(None)?.join(MOD_SETTINGS),
+        };
+
+        match &self.command {
+            ModSettingsSubcommand::Dump => {
+                let settings = ModSettings::read(&file)?;
+                println!("{}", serde_json::to_string_pretty(&settings)?);
+            }
+            ModSettingsSubcommand::Set { json } => {
+                let reader: Box<dyn Read> = if json.as_os_str() == "-" {
+                    Box::new(io::stdin().lock())
+                } else {
+                    Box::new(BufReader::new(File::open(json)?))
+                };
+                let settings: ModSettings = serde_json::from_reader(reader)?;
+
+                info!("writing {}", file.display());
+                settings.write(&file)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use clap::{Parser, Subcommand};
+use eyre::{bail, Result};
+use factorio_mod_api::{
+    api::{ApiToken, ModDependency, ModDependencyFlavor, ModRelease},
+    mods_dir::{InstalledMod, ModList, ModsDirectory},
+    resolver::is_builtin,
+    ModPortalClient,
+};
+use semver::{Comparator, Op, Prerelease, Version};
+use tracing::{info, warn};
+
+use super::download_mod::download_progress_bar;
+use crate::App;
+
+/// Manage the mods of a Factorio installation
+#[derive(Debug, Parser)]
+pub struct ModsCommand {
+    /// The `mods` directory to manage. Defaults to `<FACTORIO_DIR>/mods` if
+    /// `--factorio-dir` is given, and to the user data directory of Factorio
+    /// otherwise.
+    #[arg(long)]
+    mods_dir: Option<PathBuf>,
+
+    /// Install and update mods for this version of Factorio, e.g. "1.1" or
+    /// "2.0.28". Defaults to the version of the configured Factorio binary, if
+    /// any.
+    #[arg(long)]
+    factorio_version: Option<String>,
+
+    #[command(subcommand)]
+    command: ModsSubcommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ModsSubcommand {
+    /// List the installed mods
+    List,
+
+    /// Enable installed mods
+    Enable {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+
+    /// Disable installed mods
+    Disable {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+
+    /// Download mods and their dependencies from the mod portal and enable
+    /// them
+    Install {
+        /// The mods to install, optionally with a version requirement, e.g.
+        /// "my-mod >= 1.2.0"
+        #[arg(required = true)]
+        mods: Vec<String>,
+    },
+
+    /// Delete mods and remove them from the mod list
+    Remove {
+        #[arg(required = true)]
+        names: Vec<String>,
+    },
+
+    /// Update mods to their latest release on the mod portal. Mods whose
+    /// version is pinned in `mod-list.json` are skipped, and new dependencies
+    /// are installed.
+    Update {
+        /// The mods to update. Defaults to all installed mods.
+        names: Vec<String>,
+    },
+}
+
+impl ModsCommand {
+    pub async fn execute(&self, app: &App) -> Result<()> {
+        let mods = ModsDirectory::new(app.mods_dir(self.mods_dir.as_ref())?);
+        info!("managing mods in {}", mods.path().display());
+
+        match &self.command {
+            ModsSubcommand::List => list(&mods),
+            ModsSubcommand::Enable { names } => set_enabled(&mods, names, true),
+            ModsSubcommand::Disable { names } => set_enabled(&mods, names, false),
+            ModsSubcommand::Install { mods: requested } => {
+                self.install(app, &mods, requested).await
+            }
+            ModsSubcommand::Remove { names } => remove(&mods, names),
+            ModsSubcommand::Update { names } => self.update(app, &mods, names).await,
+        }
+    }
+
+    async fn install(&self, app: &App, mods: &ModsDirectory, requested: &[String]) -> Result<()> {
+        let requested = requested
+            .iter()
+            .map(|spec| ModDependency::try_from(spec.as_str()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let installed = mods.installed_mods()?;
+        let mut mod_list = mods.read_mod_list()?;
+        let requested_names: Vec<&str> = requested.iter().map(|d| d.name.as_str()).collect();
+        let mut requirements = requested.clone();
+        requirements.extend(installed_requirements(&installed, &mod_list, &requested_names));
+
+        let options = app.resolve_options(self.factorio_version.as_deref())?;
+        let client = app.mod_portal_client(false)?;
+        let resolution = client.resolve_mods(&requirements, &options).await?;
+
+        let token = app.api_token()?;
+        for (name, release) in &resolution.releases {
+            if installed.iter().any(|m| m.info.name == *name && m.info.version == release.version) {
+                info!("{name} {} is already installed", release.version);
+            } else {
+                download(&client, &token, release, mods).await?;
+                remove_other_versions(mods, name, &release.version)?;
+            }
+            mod_list.set_enabled(name, true);
+        }
+
+        mods.write_mod_list(&mod_list)?;
+        Ok(())
+    }
+
+    async fn update(&self, app: &App, mods: &ModsDirectory, names: &[String]) -> Result<()> {
+        let options = app.resolve_options(self.factorio_version.as_deref())?;
+        let client = app.mod_portal_client(false)?;
+        let installed = mods.installed_mods()?;
+        let mut mod_list = mods.read_mod_list()?;
+
+        let outdated = client.outdated_mods(mods, options.factorio_version).await?;
+        let outdated =
+            outdated.iter().filter(|(m, _)| names.is_empty() || names.contains(&m.info.name));
+
+        let mut requirements = Vec::new();
+        for (outdated, _) in outdated {
+            let name = &outdated.info.name;
+            if let Some(version) = mod_list.get(name).and_then(|entry| entry.version.as_ref()) {
+                info!("skipping {name}, which is pinned to version {version}");
+                continue;
+            }
+            requirements.push(ModDependency {
+                flavor: ModDependencyFlavor::Normal,
+                name: name.clone(),
+                comparator: Some(comparator(Op::GreaterEq, &outdated.info.version)),
+            });
+        }
+        if requirements.is_empty() {
+            info!("all mods are up to date");
+            return Ok(());
+        }
+
+        // The updates may need newer versions of other mods, or new mods.
+        let updated: Vec<&str> = requirements.iter().map(|d| d.name.as_str()).collect();
+        let others = installed_requirements(&installed, &mod_list, &updated);
+        requirements.extend(others);
+        let resolution = client.resolve_mods(&requirements, &options).await?;
+
+        let token = app.api_token()?;
+        for (name, release) in &resolution.releases {
+            let versions = installed.iter().filter(|m| m.info.name == *name);
+            match versions.map(|m| &m.info.version).max() {
+                Some(version) if *version == release.version => continue,
+                Some(version) => info!("updating {name} from {version} to {}", release.version),
+                None => {
+                    info!("installing {name} {}, which the updates depend on", release.version);
+                    mod_list.set_enabled(name, true);
+                }
+            }
+
+            download(&client, &token, release, mods).await?;
+            remove_other_versions(mods, name, &release.version)?;
+        }
+
+        mods.write_mod_list(&mod_list)?;
+        Ok(())
+    }
+}
+
+/// Optional requirements on the installed mods, which keep them at the version
+/// pinned in `mod-list.json`, or at least at their installed version, if they
+/// are part of a resolution. Mods in `except` may be downgraded unless they are
+/// pinned.
+fn installed_requirements(
+    installed: &[InstalledMod],
+    mod_list: &ModList,
+    except: &[&str],
+) -> Vec<ModDependency> {
+    // Mods are sorted by version, so this keeps the newest one.
+    let newest: BTreeMap<&str, &Version> =
+        installed.iter().map(|m| (m.info.name.as_str(), &m.info.version)).collect();
+
+    newest
+        .into_iter()
+        .filter_map(|(name, version)| {
+            let comparator = match mod_list.get(name).and_then(|entry| entry.version.as_ref()) {
+                Some(pinned) => comparator(Op::Exact, pinned),
+                None if except.contains(&name) => return None,
+                None => comparator(Op::GreaterEq, version),
+            };
+            Some(ModDependency {
+                flavor: ModDependencyFlavor::Optional,
+                name: name.into(),
+                comparator: Some(comparator),
+            })
+        })
+        .collect()
+}
+
+fn comparator(op: Op, version: &Version) -> Comparator {
+    Comparator {
+        op,
+        major: version.major,
+        minor: Some(version.minor),
+        patch: Some(version.patch),
+        pre: Prerelease::EMPTY,
+    }
+}
+
+/// Deletes the installed versions of a mod other than `version`.
+fn remove_other_versions(mods: &ModsDirectory, name: &str, version: &Version) -> Result<()> {
+    for old in mods.installed_versions(name)? {
+        if old.info.version == *version {
+            continue;
+        }
+        if old.path.is_dir() {
+            warn!("leaving unpacked mod {} in place", old.path.display());
+        } else {
+            info!("removing {}", old.path.display());
+            fs::remove_file(&old.path)?;
+        }
+    }
+    Ok(())
+}
+
+fn list(mods: &ModsDirectory) -> Result<()> {
+    let mod_list = mods.read_mod_list()?;
+    for installed in mods.installed_mods()? {
+        let info = &installed.info;
+        let status = if mod_list.is_enabled(&info.name) { "" } else { " (disabled)" };
+        println!("{} {}{status}", info.name, info.version);
+    }
+    Ok(())
+}
+
+fn set_enabled(mods: &ModsDirectory, names: &[String], enabled: bool) -> Result<()> {
+    let mut mod_list = mods.read_mod_list()?;
+    for name in names {
+        if !is_builtin(name) && mods.installed_versions(name)?.is_empty() {
+            bail!("mod '{name}' is not installed");
+        }
+        mod_list.set_enabled(name, enabled);
+    }
+
+    mods.write_mod_list(&mod_list)?;
+    Ok(())
+}
+
+fn remove(mods: &ModsDirectory, names: &[String]) -> Result<()> {
+    for name in names {
+        if mods.installed_versions(name)?.is_empty() {
+            bail!("mod '{name}' is not installed");
+        }
+    }
+
+    let mut mod_list = mods.read_mod_list()?;
+    for name in names {
+        for path in mods.remove(name)? {
+            info!("removed {}", path.display());
+        }
+        mod_list.remove(name);
+    }
+
+    mods.write_mod_list(&mod_list)?;
+    Ok(())
+}
+
+async fn download(
+    client: &ModPortalClient,
+    token: &ApiToken,
+    release: &ModRelease,
+    mods: &ModsDirectory,
+) -> Result<()> {
+    fs::create_dir_all(mods.path())?;
+
+    let progress = download_progress_bar(&release.file_name);
+    client
+        .download_release_with_progress(release, token, mods.path(), |p| {
+            if let Some(total) = p.total {
+                progress.set_length(total);
+            }
+            progress.set_position(p.received);
+        })
+        .await?;
+    progress.finish();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use factorio_mod_api::{
+        api::ModDependency,
+        archive::ModInfo,
+        mods_dir::{InstalledMod, ModList, ModListEntry},
+    };
+
+    use super::installed_requirements;
+
+    #[test]
+    fn requirements_on_installed_mods() -> Result<(), Box<dyn std::error::Error>> {
+        let installed = |name: &str, version: &str| -> Result<_, Box<dyn std::error::Error>> {
+            let info =
+                serde_json::json!({ "name": name, "version": version, "title": "", "author": "" });
+            Ok(InstalledMod {
+                info: serde_json::from_value::<ModInfo>(info)?,
+                path: PathBuf::new(),
+            })
+        };
+        let installed = [
+            installed("mod-a", "1.0.0")?,
+            installed("mod-a", "1.2.0")?,
+            installed("mod-b", "2.0.0")?,
+            installed("mod-c", "3.0.0")?,
+            installed("mod-d", "4.0.0")?,
+        ];
+        let mut mod_list = ModList::default();
+        mod_list.mods.push(ModListEntry {
+            name: "mod-b".into(),
+            enabled: true,
+            version: Some("1.0.0".parse()?),
+        });
+
+        let requirements = installed_requirements(&installed, &mod_list, &["mod-b", "mod-c"]);
+
+        assert_eq!(
+            requirements,
+            [
+                ModDependency::try_from("? mod-a >= 1.2.0")?,
+                ModDependency::try_from("? mod-b = 1.0.0")?,
+                ModDependency::try_from("? mod-d >= 4.0.0")?,
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use eyre::Result;
+use factorio_mod_api::archive::read_info_json;
+
+use crate::App;
+
+/// Upload a new release of a mod to the mod portal.
+#[derive(Debug, Parser)]
+pub struct PublishCommand {
+    /// The mod archive to upload. Name and version are read from its
+    /// `info.json`.
+    archive: PathBuf,
+
+    /// API key with the "ModPortal: Upload Mods" permission. Can also be set in
+    /// the config file.
+    #[arg(long, env = "FACTORIO_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+}
+
+impl PublishCommand {
+    pub async fn execute(&self, app: &App) -> Result<()> {
+        let info = read_info_json(&self.archive)?;
+        let api_key = app.api_key(self.api_key.as_deref())?;
+
+        app.mod_portal_client(false)?.upload_release(&info.name, &self.archive, &api_key).await?;
+
+        println!("Published {} {}", info.name, info.version);
+        Ok(())
+    }
+}
@@ -1,134 +1,221 @@
-use std::collections::HashMap;
-
-use clap::Parser;
-use eyre::{bail, eyre, Result};
-use factorio_mod_api::api::{ModDependency, ModRelease};
-use factorio_mod_api::ModPortalClient;
-use itertools::Itertools;
-use semver::Version;
-use tracing::{debug, trace};
+use std::{collections::HashSet, iter, path::PathBuf};
+
+use clap::{Parser, ValueEnum};
+use eyre::Result;
+use factorio_mod_api::{
+    api::{ModDependency, ModDependencyFlavor},
+    lockfile::Lockfile,
+    resolver::{is_builtin, Resolution},
+};
+use serde_json::json;
+use tracing::info;
 
 use crate::App;
 
+#[derive(Clone, Debug, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+enum OutputFormat {
+    /// One line per mod with its version
+    Text,
+    /// A graph in Graphviz DOT format
+    Dot,
+    /// Mods and dependency edges as JSON
+    Json,
+}
+
 /// Lists all dependencies of a set of mods, trying to find compatible
 /// versions
 #[derive(Debug, Parser)]
 pub struct ResolveModsCommand {
     /// A list of mods, optionally with version requirements
     mods: Vec<String>,
+
+    /// Only use cached mod portal responses, don't access the network
+    #[arg(long)]
+    offline: bool,
+
+    /// Resolve mods for this version of Factorio, e.g. "1.1" or "2.0.28".
+    /// Only mod releases made for it are considered, and dependencies on
+    /// built-in mods are checked against it. Defaults to the version of the
+    /// configured Factorio binary, if any.
+    #[arg(long)]
+    factorio_version: Option<String>,
+
+    /// Write the resolved releases to a lockfile, which can be installed with
+    /// `fct install --locked`
+    #[arg(long, value_name = "LOCKFILE")]
+    lock: Option<PathBuf>,
+
+    /// Show the dependencies as a tree, indicating which mod required which
+    #[arg(long, conflicts_with = "format")]
+    tree: bool,
+
+    /// Format of the output
+    #[arg(long, short, default_value = "text")]
+    format: OutputFormat,
 }
 
 impl ResolveModsCommand {
-    pub async fn execute(&self, _app: &App) -> Result<()> {
+    pub async fn execute(&self, app: &App) -> Result<()> {
         let mods: factorio_mod_api::Result<Vec<ModDependency>> =
             self.mods.iter().map(|a| ModDependency::try_from(a.as_str())).collect();
 
-        let resolutions = ModVersionResolver::new().unwrap().resolve(mods?).await?;
+        let options = app.resolve_options(self.factorio_version.as_deref())?;
+        let client = app.mod_portal_client(self.offline)?;
+        let resolution = client.resolve_mods(&mods?, &options).await?;
+
+        if self.tree {
+            print_tree(&resolution);
+        } else {
+            match self.format {
+                OutputFormat::Text => {
+                    for (mod_name, release) in &resolution.releases {
+                        println!("{mod_name} {}", release.version);
+                    }
+                }
+                OutputFormat::Dot => print!("{}", to_dot(&resolution)),
+                OutputFormat::Json => println!("{:#}", to_json(&resolution)),
+            }
+        }
+
+        if !resolution.unchecked_builtins.is_empty() {
+            eprintln!(
+                "warning: the Factorio version is unknown (see `--factorio-version`), so these \
+                 requirements weren't checked:"
+            );
+            for requirement in &resolution.unchecked_builtins {
+                eprintln!("  {requirement}");
+            }
+        }
 
-        for (mod_name, version) in resolutions.iter().sorted_by_key(|&(name, _)| name) {
-            println!("{mod_name} {version}");
+        if let Some(path) = &self.lock {
+            info!("writing lockfile {}", path.display());
+            Lockfile::from(&resolution).write(path)?;
         }
 
         Ok(())
     }
 }
 
-/// A component that tries to find a set of compatible mod versions for a set of
-/// given mods and their transitive dependencies. The algorithm uses a greedy
-/// heuristic that cannot exhaustively find any potential solution.
-///
-/// Note: This is pretty hacky at the moment and mostly intended to be a
-/// demonstration of the [`ModPortalClient`].
-pub struct ModVersionResolver {
-    client: ModPortalClient,
-    outstanding: Vec<ModDependency>,
-    resolutions: HashMap<String, ModRelease>,
+/// Prints the dependencies in the style of `cargo tree`. Mods whose
+/// dependencies were already shown are marked with `(*)`.
+fn print_tree(resolution: &Resolution) {
+    let mut seen = HashSet::new();
+    for dep in &resolution.requested {
+        println!("{}", describe(resolution, dep, &seen));
+        print_subtree(resolution, dep, "", &mut seen);
+    }
 }
 
-impl ModVersionResolver {
-    pub fn new() -> Result<ModVersionResolver> {
-        Ok(ModVersionResolver {
-            resolutions: HashMap::new(),
-            outstanding: Vec::new(),
-            client: ModPortalClient::new()?,
-        })
+fn print_subtree<'a>(
+    resolution: &'a Resolution,
+    dep: &'a ModDependency,
+    prefix: &str,
+    seen: &mut HashSet<&'a str>,
+) {
+    if !is_expanded(resolution, dep) || seen.contains(dep.name.as_str()) {
+        return;
     }
+    seen.insert(&dep.name);
+
+    let deps = resolution.dependencies_of(Some(&dep.name));
+    for (i, child) in deps.iter().enumerate() {
+        let (branch, indent) =
+            if i + 1 == deps.len() { ("└── ", "    ") } else { ("├── ", "│   ") };
+        println!("{prefix}{branch}{}", describe(resolution, child, seen));
+        print_subtree(resolution, child, &format!("{prefix}{indent}"), seen);
+    }
+}
 
-    /// Resolves the dependencies of a set of mods.
-    pub async fn resolve(mut self, deps: Vec<ModDependency>) -> Result<HashMap<String, Version>> {
-        self.outstanding = deps;
-        let mut done = Vec::new();
-        while let Some(dep) = self.outstanding.pop() {
-            self.resolve_mod(&dep).await?;
-            done.push(dep);
-        }
+/// Whether the dependencies of the target of `dep` are shown below it.
+fn is_expanded(resolution: &Resolution, dep: &ModDependency) -> bool {
+    dep.flavor != ModDependencyFlavor::Incompatibility
+        && resolution.releases.contains_key(&dep.name)
+}
 
-        for (mod_name, release) in &self.resolutions {
-            for dep in release
-                .info_json
-                .dependencies
-                .as_ref()
-                .expect("full ModSpecs must contain dependencies")
-                .iter()
-                .filter(|d| d.name != "base")
-            {
-                use factorio_mod_api::api::ModDependencyFlavor::*;
-                let dep_name = &dep.name;
-
-                if let Some(resolution) = self.resolutions.get(dep_name) {
-                    let version = &resolution.version;
-
-                    if let Incompatibility = dep.flavor {
-                        bail!("mod '{mod_name}' is incompatible with '{dep_name}'")
-                    }
+/// Describes a dependency and the release selected for it, e.g.
+/// `? bravo >= 1.0.0 (2.0.0)`.
+fn describe(resolution: &Resolution, dep: &ModDependency, seen: &HashSet<&str>) -> String {
+    let status = match resolution.releases.get(&dep.name) {
+        _ if dep.flavor == ModDependencyFlavor::Incompatibility => String::new(),
+        Some(release) if seen.contains(dep.name.as_str()) => format!(" ({}) (*)", release.version),
+        Some(release) => format!(" ({})", release.version),
+        None if is_builtin(&dep.name) => " (built-in)".into(),
+        None => " (not selected)".into(),
+    };
+    format!("{dep}{status}")
+}
 
-                    if let Some(c) = &dep.comparator {
-                        if !c.matches(version) {
-                            bail!("'{mod_name}': dependency '{dep}' is incompatible with '{dep_name}' version {version}' ")
-                        }
-                    }
-                } else if let Normal | NoEffectOnLoadOrder = dep.flavor {
-                    bail!("'{mod_name}': required dependency '{dep_name}' missing",)
-                }
-            }
-        }
+/// All dependency edges, starting either at a selected mod or at the requested
+/// mod set (`None`).
+fn edges(resolution: &Resolution) -> impl Iterator<Item = (Option<&str>, &ModDependency)> {
+    iter::once(None)
+        .chain(resolution.releases.keys().map(|name| Some(name.as_str())))
+        .flat_map(|from| resolution.dependencies_of(from).iter().map(move |dep| (from, dep)))
+}
 
-        Ok(self.resolutions.into_iter().map(|(n, r)| (n, r.version)).collect())
+fn flavor_name(flavor: &ModDependencyFlavor) -> &'static str {
+    use ModDependencyFlavor::*;
+    match flavor {
+        Normal => "required",
+        Incompatibility => "incompatible",
+        Optional => "optional",
+        Hidden => "hidden-optional",
+        NoEffectOnLoadOrder => "no-load-order",
     }
+}
 
-    pub async fn resolve_mod(&mut self, d: &ModDependency) -> Result<()> {
-        let name = &d.name;
-        debug!("resolving {name}");
-
-        let spec = self.client.get_mod_spec(name).await?;
-
-        let release = spec
-            .short_spec
+fn to_json(resolution: &Resolution) -> serde_json::Value {
+    json!({
+        "mods": resolution
             .releases
             .iter()
-            .filter(|r| d.comparator.as_ref().map(|v| v.matches(&r.version)).unwrap_or(true))
-            .max_by_key(|r| &r.version)
-            .ok_or_else(|| eyre!("{name}: could not resolve version {:?}", d.comparator))?
-            .clone();
-
-        for dependency in release
-            .info_json
-            .dependencies
-            .as_ref()
-            .expect("full ModSpecs must contain dependencies")
-        {
-            if dependency.is_required()
-                && dependency.name != "base"
-                && !self.resolutions.contains_key(&dependency.name)
-            {
-                let dep_name = &dependency.name;
-                trace!("enqueuing {dep_name}");
-                self.outstanding.push(dependency.clone());
-            }
-        }
+            .map(|(name, release)| json!({ "name": name, "version": release.version }))
+            .collect::<Vec<_>>(),
+        "edges": edges(resolution)
+            .map(|(from, dep)| json!({
+                "from": from,
+                "to": dep.name,
+                "flavor": flavor_name(&dep.flavor),
+                "comparator": dep.comparator.as_ref().map(|c| c.to_string()),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
 
-        self.resolutions.insert(name.into(), release);
+/// Renders the dependency graph in Graphviz DOT format. Optional dependencies
+/// are dashed, incompatibilities red, and mods that aren't part of the
+/// resolution dotted.
+fn to_dot(resolution: &Resolution) -> String {
+    const REQUESTED: &str = "(requested)";
 
-        Ok(())
+    let mut dot = String::from("digraph mods {\n");
+    dot += &format!("    \"{REQUESTED}\" [shape=box];\n");
+    for (name, release) in &resolution.releases {
+        dot += &format!("    \"{name}\" [label=\"{name} {}\"];\n", release.version);
     }
+
+    let mut declared = HashSet::new();
+    for (_, dep) in edges(resolution) {
+        if !resolution.releases.contains_key(&dep.name) && declared.insert(&dep.name) {
+            dot += &format!("    \"{}\" [style=dotted];\n", dep.name);
+        }
+    }
+
+    for (from, dep) in edges(resolution) {
+        let label = dep.comparator.as_ref().map(|c| c.to_string()).unwrap_or_default();
+        let style = match dep.flavor {
+            ModDependencyFlavor::Incompatibility => ", color=red",
+            ModDependencyFlavor::Optional | ModDependencyFlavor::Hidden => ", style=dashed",
+            _ => "",
+        };
+        dot += &format!(
+            "    \"{}\" -> \"{}\" [label=\"{label}\"{style}];\n",
+            from.unwrap_or(REQUESTED),
+            dep.name
+        );
+    }
+
+    dot += "}\n";
+    dot
 }
@@ -1,17 +1,26 @@
 mod commands;
 mod settings;
 
-use std::path::PathBuf;
+use std::{fs::File, io::BufReader, path::PathBuf, process::Command, time::Duration};
 
 use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 use commands::{
-    download_mod::DownloadModCommand, export::ExportCommand, login::LoginCommand,
-    resolve_mods::ResolveModsCommand,
+    cache::CacheCommand, download_mod::DownloadModCommand, export::ExportCommand,
+    install::InstallCommand, login::LoginCommand, mod_settings::ModSettingsCommand,
+    mods::ModsCommand, publish::PublishCommand, resolve_mods::ResolveModsCommand,
 };
-use directories::ProjectDirs;
-use eyre::Result;
+use directories::{BaseDirs, ProjectDirs};
+use eyre::{bail, eyre, Result};
+use factorio_exporter::parse_version_output;
+use factorio_mod_api::{
+    api::{ApiKey, ApiToken},
+    cache::DiskCache,
+    resolver::ResolveOptions,
+    ModPortalClient,
+};
+use semver::Version;
 use settings::Settings;
-use tracing::info;
+use tracing::{debug, info};
 
 /// A collection of tools for Factorio (<http://www.factorio.com>)
 #[derive(Parser, Debug)]
@@ -38,7 +47,12 @@ enum Commands {
     Export(ExportCommand),
     ResolveMods(ResolveModsCommand),
     DownloadMod(DownloadModCommand),
+    Install(InstallCommand),
+    Mods(ModsCommand),
+    ModSettings(ModSettingsCommand),
     Login(LoginCommand),
+    Publish(PublishCommand),
+    Cache(CacheCommand),
 }
 
 pub struct App {
@@ -56,6 +70,9 @@ const FACTORIO_BINPATH: &str = "bin/x64/factorio";
 
 const TOOL_NAME: &str = "fct";
 const CONFIG_NAME: &str = "config";
+const EXPORT_CACHE_DIR: &str = "exports";
+const MOD_PORTAL_CACHE_DIR: &str = "mod-portal";
+const MODS_DIR: &str = "mods";
 
 impl App {
     fn new() -> Result<App> {
@@ -69,7 +86,12 @@ impl App {
             Commands::Export(cmd) => cmd.execute(&self).await?,
             Commands::ResolveMods(cmd) => cmd.execute(&self).await?,
             Commands::DownloadMod(cmd) => cmd.execute(&self).await?,
+            Commands::Install(cmd) => cmd.execute(&self).await?,
+            Commands::Mods(cmd) => cmd.execute(&self).await?,
+            Commands::ModSettings(cmd) => cmd.execute(&self).await?,
             Commands::Login(cmd) => cmd.execute(&self).await?,
+            Commands::Publish(cmd) => cmd.execute(&self).await?,
+            Commands::Cache(cmd) => cmd.execute(&self).await?,
         }
         Ok(())
     }
@@ -78,13 +100,18 @@ impl App {
         self.args.factorio_dir.as_ref().or(self.settings.paths.factorio_dir.as_ref())
     }
 
-    fn factorio_binary(&self) -> Result<PathBuf> {
-        Ok(self
-            .args
+    /// The Factorio binary from the command line or the config file, if any.
+    fn configured_factorio_binary(&self) -> Option<PathBuf> {
+        self.args
             .factorio_binary
             .clone()
             .or_else(|| self.settings.paths.factorio_binary.clone())
             .or_else(|| self.factorio_dir().map(|d| d.join(FACTORIO_BINPATH)))
+    }
+
+    fn factorio_binary(&self) -> Result<PathBuf> {
+        Ok(self
+            .configured_factorio_binary()
             .inspect(|path| {
                 if !path.exists() {
                     Args::command()
@@ -106,9 +133,107 @@ impl App {
             })?)
     }
 
+    /// The `mods` directory to manage: the one given on the command line or in
+    /// the config file, the one next to the Factorio installation, or the
+    /// default location in the user's data directory.
+    fn mods_dir(&self, arg: Option<&PathBuf>) -> Result<PathBuf> {
+        if let Some(dir) = arg.or(self.settings.paths.mods_dir.as_ref()) {
+            return Ok(dir.clone());
+        }
+        if let Some(dir) = self.factorio_dir() {
+            return Ok(dir.join(MODS_DIR));
+        }
+
+        let dirs = BaseDirs::new().ok_or_else(|| eyre!("Home directory not found"))?;
+        let user_data_dir = if cfg!(target_os = "linux") {
+            dirs.home_dir().join(".factorio")
+        } else if cfg!(windows) {
+            dirs.data_dir().join("Factorio")
+        } else {
+            dirs.data_dir().join("factorio")
+        };
+        Ok(user_data_dir.join(MODS_DIR))
+    }
+
+    /// Detects the version of the configured Factorio binary. Returns `None`
+    /// if no binary is configured.
+    fn factorio_version(&self) -> Result<Option<Version>> {
+        let Some(binary) = self.configured_factorio_binary().filter(|b| b.is_file()) else {
+            return Ok(None);
+        };
+
+        debug!("detecting version of {}", binary.display());
+        let output = Command::new(&binary).arg("--version").output()?;
+        Ok(Some(parse_version_output(&String::from_utf8_lossy(&output.stdout))?))
+    }
+
+    /// Options for resolving mods for the Factorio version given on the
+    /// command line, either as "major.minor" or as a full version. Defaults to
+    /// the version of the configured Factorio binary.
+    fn resolve_options(&self, factorio_version: Option<&str>) -> Result<ResolveOptions> {
+        let options = match factorio_version {
+            Some(version) => match Version::parse(version) {
+                Ok(version) => ResolveOptions::for_game_version(&version),
+                Err(_) => ResolveOptions {
+                    factorio_version: Some(version.parse()?),
+                    ..Default::default()
+                },
+            },
+            None => self
+                .factorio_version()?
+                .map(|version| ResolveOptions::for_game_version(&version))
+                .unwrap_or_default(),
+        };
+
+        if let Some(version) = options.factorio_version {
+            info!("resolving mods for Factorio {version}");
+        }
+        Ok(options)
+    }
+
+    /// The directory where `fct export` caches its results.
+    fn export_cache_dir(&self) -> PathBuf {
+        self.dirs.cache_dir().join(EXPORT_CACHE_DIR)
+    }
+
+    /// Creates a mod portal client that caches responses on disk.
+    fn mod_portal_client(&self, offline: bool) -> Result<ModPortalClient> {
+        let settings = &self.settings.mod_portal;
+        let cache_dir = settings
+            .cache_dir
+            .clone()
+            .unwrap_or_else(|| self.dirs.cache_dir().join(MOD_PORTAL_CACHE_DIR));
+
+        let mut cache = DiskCache::new(cache_dir).offline(offline);
+        if let Some(ttl) = settings.cache_ttl {
+            cache = cache.with_ttl(Duration::from_secs(ttl));
+        }
+
+        Ok(ModPortalClient::new()?.with_disk_cache(cache))
+    }
+
+    /// The API key for the publishing APIs, either passed on the command line
+    /// or from the config file.
+    fn api_key(&self, arg: Option<&str>) -> Result<ApiKey> {
+        arg.map(String::from)
+            .or_else(|| self.settings.mod_portal.api_key.clone())
+            .map(ApiKey)
+            .ok_or_else(|| eyre!("No API key found. Please use `--api-key` or the config file."))
+    }
+
     fn api_token_path(&self) -> PathBuf {
         self.dirs.config_dir().join("api_token.json")
     }
+
+    /// The login token stored by `fct login`.
+    fn api_token(&self) -> Result<ApiToken> {
+        let token_file = self.api_token_path();
+        if !token_file.exists() {
+            bail!("API token not found. Please use `fct login` first.");
+        }
+
+        Ok(serde_json::from_reader(BufReader::new(File::open(token_file)?))?)
+    }
 }
 
 #[tokio::main]
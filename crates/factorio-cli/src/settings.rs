@@ -9,12 +9,28 @@ use tracing::debug;
 pub struct Settings {
     #[serde(default)]
     pub paths: PathSettings,
+
+    #[serde(default)]
+    pub mod_portal: ModPortalSettings,
 }
 
 #[derive(Debug, Default, Deserialize)]
 pub struct PathSettings {
     pub factorio_dir: Option<PathBuf>,
     pub factorio_binary: Option<PathBuf>,
+    pub mods_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ModPortalSettings {
+    pub cache_dir: Option<PathBuf>,
+
+    /// Number of seconds during which cached mod portal responses are used
+    /// without asking the mod portal whether they changed.
+    pub cache_ttl: Option<u64>,
+
+    /// API key for publishing mods.
+    pub api_key: Option<String>,
 }
 
 impl Settings {
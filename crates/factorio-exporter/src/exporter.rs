@@ -1,24 +1,36 @@
 use std::{
+    cell::{OnceCell, RefCell},
+    collections::BTreeMap,
     fs::{self, File},
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Output},
 };
 
+use factorio_mod_api::{
+    mod_settings::{ModSettings, PropertyTree},
+    mods_dir::ModList,
+};
 use indoc::writedoc;
+use semver::Version;
 use serde_json::Value;
+use sha1::{Digest, Sha1};
 use tempfile::TempDir;
 use tracing::{debug, error, info};
 
 use crate::{
     internal::mod_controller::ModController,
+    locale::PrototypeLocale,
+    prototypes::Prototypes,
     FactorioExporterError::{self, FactorioExecutionError},
     Result,
 };
 
 const CONFIG: &str = "config.ini";
+const ICON_EXTENSION: &str = "png";
 const MOD_NAME: &str = "factorio_exporter";
 const MODS_DIR: &str = "mods";
+const SCRIPT_OUTPUT: &str = "script-output";
 
 /// Main class for orchestrating the export.
 pub struct FactorioExporter<'a> {
@@ -26,6 +38,11 @@ pub struct FactorioExporter<'a> {
     locale: &'a str,
     temp_dir: TempDir,
     mod_controller: ModController,
+    mod_settings: Option<ModSettings>,
+    mods_enabled: BTreeMap<String, bool>,
+    cache_dir: Option<PathBuf>,
+    version_output: OnceCell<String>,
+    mods_hash: RefCell<Option<String>>,
 }
 
 impl FactorioExporter<'_> {
@@ -39,7 +56,17 @@ impl FactorioExporter<'_> {
     pub fn new<'a>(factorio_binary: &'a Path, locale: &'a str) -> Result<FactorioExporter<'a>> {
         let temp_dir = tempfile::Builder::new().prefix(MOD_NAME).tempdir()?;
         let mod_controller = ModController::new(temp_dir.path().join(MODS_DIR));
-        Ok(FactorioExporter { factorio_binary, locale, temp_dir, mod_controller })
+        Ok(FactorioExporter {
+            factorio_binary,
+            locale,
+            temp_dir,
+            mod_controller,
+            mod_settings: None,
+            mods_enabled: BTreeMap::new(),
+            cache_dir: None,
+            version_output: OnceCell::new(),
+            mods_hash: RefCell::new(None),
+        })
     }
 }
 
@@ -50,19 +77,140 @@ impl FactorioExporter<'_> {
     /// them into a [`serde_yaml::Value`] object, which can easily deserialized
     /// of serialized into other data types further.
     pub fn export(&self) -> Result<Value> {
-        self.create_exec_dir()?;
-
-        info!("create an empty save file");
-        self.run_factorio(&["--dump-data"])?;
+        info!("dumping prototype data");
+        Ok(serde_json::from_slice(&self.dump("--dump-data", "data-raw-dump.json", None)?)?)
+    }
 
-        Ok(serde_json::from_slice(&fs::read(
-            self.temp_dir.path().join("script-output/data-raw-dump.json"),
+    /// Export the translated names and descriptions of all prototypes, in the
+    /// locale given to [`FactorioExporter::new`]. Use
+    /// [`PrototypeLocale::apply`] to add them to the result of
+    /// [`FactorioExporter::export`].
+    pub fn export_locale(&self) -> Result<PrototypeLocale> {
+        info!("dumping prototype locale: {}", self.locale);
+        Ok(serde_json::from_slice(&self.dump(
+            "--dump-prototype-locale",
+            "prototype-locale.json",
+            Some(self.locale),
         )?)?)
     }
 
+    /// Export the icons of all prototypes as PNG images. The result maps the
+    /// type and name of each prototype to its image, which is stored in the
+    /// temporary execution directory, so it needs to be copied elsewhere before
+    /// the exporter is dropped.
+    ///
+    /// This requires the full Factorio binary, because the headless one
+    /// doesn't load any graphics.
+    pub fn export_icons(&self) -> Result<BTreeMap<(String, String), PathBuf>> {
+        self.prepare()?;
+
+        info!("dumping icon sprites");
+        self.run_factorio(&["--dump-icon-sprites"])?;
+
+        // The icons are stored as `<type>/<name>.png` files.
+        let mut icons = BTreeMap::new();
+        for type_dir in fs::read_dir(self.temp_dir.path().join(SCRIPT_OUTPUT))? {
+            let type_dir = type_dir?;
+            if !type_dir.file_type()?.is_dir() {
+                continue;
+            }
+            let prototype_type = type_dir.file_name().to_string_lossy().into_owned();
+
+            for file in fs::read_dir(type_dir.path())? {
+                let path = file?.path();
+                if path.extension().is_some_and(|ext| ext == ICON_EXTENSION) {
+                    if let Some(name) = path.file_stem() {
+                        let name = name.to_string_lossy().into_owned();
+                        icons.insert((prototype_type.clone(), name), path);
+                    }
+                }
+            }
+        }
+        Ok(icons)
+    }
+
+    /// Export the prototype definitions like [`FactorioExporter::export`], and
+    /// convert them into typed [`Prototypes`].
+    pub fn export_prototypes(&self) -> Result<Prototypes> {
+        Prototypes::from_value(self.export()?)
+    }
+
+    /// Stores the results of [`FactorioExporter::export`] and
+    /// [`FactorioExporter::export_locale`] in `directory`, and reuses them
+    /// without running Factorio as long as the Factorio version, the installed
+    /// mods and the mod settings stay the same, and for the translations also
+    /// the locale. Icons are not cached.
+    pub fn set_cache_dir(&mut self, directory: impl Into<PathBuf>) {
+        self.cache_dir = Some(directory.into());
+    }
+
+    /// Runs Factorio with a dump option and returns the contents of the file
+    /// that it writes into `script-output`, or the cached contents from an
+    /// earlier run. `locale` is the locale that the file depends on, if any.
+    fn dump(&self, option: &str, file: &str, locale: Option<&str>) -> Result<Vec<u8>> {
+        let cache_file = match &self.cache_dir {
+            Some(dir) => Some(dir.join(self.cache_key(locale)?).join(file)),
+            None => None,
+        };
+        if let Some(path) = cache_file.as_ref().filter(|path| path.is_file()) {
+            info!("using cached {}", path.display());
+            return Ok(fs::read(path)?);
+        }
+
+        self.prepare()?;
+        self.run_factorio(&[option])?;
+        let contents = fs::read(self.temp_dir.path().join(SCRIPT_OUTPUT).join(file))?;
+
+        if let Some(path) = cache_file {
+            debug!("caching {}", path.display());
+
+            // Write to a temporary file first, so that concurrent exports
+            // never see a partially written cache entry.
+            let dir = path.parent().unwrap_or(&path);
+            fs::create_dir_all(dir)?;
+            let mut temp_file = tempfile::NamedTempFile::new_in(dir)?;
+            temp_file.write_all(&contents)?;
+            temp_file.persist(&path).map_err(|e| e.error)?;
+        }
+        Ok(contents)
+    }
+
+    /// A hash of everything that influences the exported data.
+    fn cache_key(&self, locale: Option<&str>) -> Result<String> {
+        let mut hasher = Sha1::new();
+        hasher.update(serde_json::to_vec(&(
+            self.version_output()?,
+            locale,
+            &self.mod_settings,
+            &self.mods_enabled,
+            self.mods_hash()?,
+        ))?);
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// The hash of the installed mods. Hashing large mods takes a while, so it
+    /// is only done once until more mods are installed.
+    fn mods_hash(&self) -> Result<String> {
+        if let Some(hash) = &*self.mods_hash.borrow() {
+            return Ok(hash.clone());
+        }
+
+        let hash = self.mod_controller.hash_mods()?;
+        *self.mods_hash.borrow_mut() = Some(hash.clone());
+        Ok(hash)
+    }
+
+    /// Sets up the execution directory. This can be done repeatedly, e.g. to
+    /// run several exports.
+    fn prepare(&self) -> Result<()> {
+        self.create_exec_dir()?;
+        self.write_mod_settings()?;
+        self.write_mod_list()
+    }
+
     fn create_exec_dir(&self) -> Result<()> {
         let config = self.temp_dir.path().join(CONFIG);
-        std::fs::create_dir(self.temp_dir.path().join("script-output"))?;
+        std::fs::create_dir_all(self.temp_dir.path().join(SCRIPT_OUTPUT))?;
 
         debug!("creating config file: {:?}", config);
         writedoc!(
@@ -89,14 +237,17 @@ impl FactorioExporter<'_> {
     ///
     /// # Arguments
     ///
-    /// * `mods` - A list of file system paths that point to Factorio mods in
-    ///   `.zip` format.
+    /// * `mods` - A list of file system paths that point to Factorio mods,
+    ///   either in `.zip` format or unpacked directories with an `info.json`
+    ///   file. A directory without `info.json` is treated as a mods folder,
+    ///   and all mods in it are installed.
     pub fn install_mods<I, P>(&self, mods: I) -> Result<()>
     where
         I: IntoIterator<Item = P>,
         P: AsRef<Path>,
     {
         info!("installing mods");
+        self.mods_hash.take();
 
         for m in mods {
             debug!("installing mod: {:?}", m.as_ref());
@@ -105,6 +256,104 @@ impl FactorioExporter<'_> {
         Ok(())
     }
 
+    /// Uses the mod settings from an existing `mod-settings.dat` file instead
+    /// of the defaults. Startup settings that were already set with
+    /// [`FactorioExporter::set_startup_settings`] take precedence.
+    pub fn load_mod_settings(&mut self, path: &Path) -> Result<()> {
+        info!("loading mod settings from {}", path.display());
+
+        let mut settings = ModSettings::read(path)?;
+        if let Some(overrides) = self.mod_settings.take() {
+            for (name, value) in overrides.startup {
+                set_value(&mut settings.startup, name, value);
+            }
+        }
+        self.mod_settings = Some(settings);
+        Ok(())
+    }
+
+    /// Overrides the values of startup settings, given as pairs of setting
+    /// name and value, e.g. from a map. Settings that aren't overridden keep
+    /// their defaults, or the value from
+    /// [`FactorioExporter::load_mod_settings`].
+    ///
+    /// If a setting is already stored as a floating point number, integer
+    /// values are converted accordingly. Otherwise the type of the value is
+    /// used as it is, so values of double settings need to be given as
+    /// [`PropertyTree::Number`].
+    pub fn set_startup_settings<I, S>(&mut self, settings: I)
+    where
+        I: IntoIterator<Item = (S, PropertyTree)>,
+        S: Into<String>,
+    {
+        for (name, value) in settings {
+            let mod_settings = self.mod_settings.get_or_insert_with(Default::default);
+            set_value(&mut mod_settings.startup, name.into(), value);
+        }
+    }
+
+    /// Enables or disables mods, given as pairs of mod name and enabled state.
+    /// This also applies to built-in mods like `quality` or `space-age`, which
+    /// allows exporting the base game without expansions. Mods that aren't
+    /// mentioned are enabled, like Factorio does by default.
+    pub fn set_mods_enabled<I, S>(&mut self, mods: I)
+    where
+        I: IntoIterator<Item = (S, bool)>,
+        S: Into<String>,
+    {
+        self.mods_enabled.extend(mods.into_iter().map(|(name, enabled)| (name.into(), enabled)));
+    }
+
+    /// Writes `mod-list.json` if any mods were explicitly enabled or disabled.
+    fn write_mod_list(&self) -> Result<()> {
+        if self.mods_enabled.is_empty() {
+            return Ok(());
+        }
+
+        let mut mod_list = ModList::default();
+        for (name, enabled) in &self.mods_enabled {
+            debug!("{} mod: {name}", if *enabled { "enabling" } else { "disabling" });
+            mod_list.set_enabled(name, *enabled);
+        }
+        self.mod_controller.write_mod_list(&mod_list)
+    }
+
+    /// Writes the mod settings, if any, into the mods directory where Factorio
+    /// reads them.
+    fn write_mod_settings(&self) -> Result<()> {
+        let Some(settings) = &self.mod_settings else {
+            return Ok(());
+        };
+
+        let mut settings = settings.clone();
+        if settings.version == [0; 4] {
+            settings.version = self.factorio_version()?;
+        }
+        self.mod_controller.write_mod_settings(&settings)
+    }
+
+    /// Detects the version of the Factorio binary, as stored in the header of
+    /// `mod-settings.dat`.
+    fn factorio_version(&self) -> Result<[u16; 4]> {
+        let stdout = self.version_output()?;
+        let version = parse_version_output(stdout)?;
+
+        match [version.major, version.minor, version.patch].map(u16::try_from) {
+            [Ok(major), Ok(minor), Ok(patch)] => Ok([major, minor, patch, 0]),
+            _ => Err(version_error(stdout)),
+        }
+    }
+
+    /// The output of `factorio --version`, which is only run once.
+    fn version_output(&self) -> Result<&str> {
+        if let Some(output) = self.version_output.get() {
+            return Ok(output);
+        }
+
+        let output = self.run_factorio(&["--version"])?;
+        Ok(self.version_output.get_or_init(|| String::from_utf8_lossy(&output.stdout).into()))
+    }
+
     fn run_factorio(&self, args: &[&str]) -> Result<Output> {
         if !self.factorio_binary.is_file() {
             return Err(FactorioExporterError::FileNotFoundError {
@@ -132,3 +381,71 @@ impl FactorioExporter<'_> {
         Ok(output)
     }
 }
+
+/// Parses the output of `factorio --version`, whose first line looks like
+/// "Version: 1.1.104 (build 59843, linux64, full)".
+pub fn parse_version_output(output: &str) -> Result<Version> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("Version:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|version| Version::parse(version).ok())
+        .ok_or_else(|| version_error(output))
+}
+
+fn version_error(output: &str) -> FactorioExporterError {
+    FactorioExporterError::FactorioOutputError {
+        message: "failed to detect the Factorio version".into(),
+        output: output.into(),
+    }
+}
+
+/// Sets a setting value, keeping the node type of floating point settings.
+fn set_value(settings: &mut BTreeMap<String, PropertyTree>, name: String, value: PropertyTree) {
+    let value = match (settings.get(&name), value) {
+        (Some(PropertyTree::Number(_)), PropertyTree::SignedInteger(v)) => {
+            PropertyTree::Number(v as f64)
+        }
+        (Some(PropertyTree::Number(_)), PropertyTree::UnsignedInteger(v)) => {
+            PropertyTree::Number(v as f64)
+        }
+        (_, value) => value,
+    };
+    settings.insert(name, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use factorio_mod_api::mod_settings::PropertyTree;
+
+    use semver::Version;
+
+    use super::{parse_version_output, set_value};
+
+    #[test]
+    fn version_output() {
+        let output = "Version: 1.1.104 (build 59843, linux64, full)\nBinary version: 64\n";
+        assert_eq!(parse_version_output(output).unwrap(), Version::new(1, 1, 104));
+        assert!(parse_version_output("Usage: factorio [OPTIONS]").is_err());
+    }
+
+    #[test]
+    fn set_value_keeps_numbers() {
+        let mut settings = BTreeMap::from([("double".to_owned(), PropertyTree::Number(1.5))]);
+
+        set_value(&mut settings, "double".into(), PropertyTree::SignedInteger(2));
+        set_value(&mut settings, "int".into(), PropertyTree::SignedInteger(2));
+        set_value(&mut settings, "new-double".into(), PropertyTree::Number(2.0));
+
+        assert_eq!(
+            settings,
+            BTreeMap::from([
+                ("double".to_owned(), PropertyTree::Number(2.0)),
+                ("int".to_owned(), PropertyTree::SignedInteger(2)),
+                ("new-double".to_owned(), PropertyTree::Number(2.0)),
+            ])
+        );
+    }
+}
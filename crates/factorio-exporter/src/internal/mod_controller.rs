@@ -1,47 +1,106 @@
 use std::{
-    fs::{self},
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Write},
     path::{Path, PathBuf},
 };
 
 use derive_builder::Builder;
+use factorio_mod_api::{
+    mod_settings::{ModSettings, MOD_SETTINGS},
+    mods_dir::{ModList, ModsDirectory, MOD_LIST},
+};
 use serde_derive::Serialize;
+use sha1::{Digest, Sha1};
 use tracing::debug;
 
 use crate::{FactorioExporterError, Result};
 
 pub struct ModController {
-    mods_dir: PathBuf,
+    mods_dir: ModsDirectory,
 }
 
 impl ModController {
     pub fn new(mods_dir: PathBuf) -> ModController {
-        ModController { mods_dir }
+        ModController { mods_dir: ModsDirectory::new(mods_dir) }
     }
 
-    #[cfg(unix)]
-    fn copy_or_link<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<()> {
-        let from = std::fs::canonicalize(from)?;
-        debug!("create symbolic link: {} -> {} ", from.display(), to.as_ref().display());
+    /// Adds a mod archive, an unpacked mod directory, or all mods in a mods
+    /// folder, i.e. a directory without `info.json`.
+    pub fn add_mod(&self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            return Err(FactorioExporterError::FileNotFoundError { file: path.into() });
+        }
 
-        std::os::unix::fs::symlink(from, to)?;
+        if path.is_dir() && !path.join("info.json").is_file() {
+            for installed in ModsDirectory::new(path).installed_mods()? {
+                debug!("adding {}", installed.path.display());
+                self.mods_dir.link_mod(&installed.path)?;
+            }
+        } else {
+            self.mods_dir.link_mod(path)?;
+        }
         Ok(())
     }
 
-    #[cfg(not(unix))]
-    fn copy_or_link<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<()> {
-        fs::copy(from, to)?;
+    pub fn write_mod_list(&self, mod_list: &ModList) -> Result<()> {
+        self.mods_dir.write_mod_list(mod_list)?;
         Ok(())
     }
 
-    pub fn add_mod(&self, path: &Path) -> Result<()> {
-        if !path.exists() || !path.is_file() {
-            return Err(FactorioExporterError::FileNotFoundError { file: path.into() });
+    pub fn write_mod_settings(&self, settings: &ModSettings) -> Result<()> {
+        fs::create_dir_all(self.mods_dir.path())?;
+        settings.write(&self.mods_dir.path().join(MOD_SETTINGS))?;
+        Ok(())
+    }
+
+    /// A hash of the names and contents of all added mods. The mod list and
+    /// mod settings are skipped, because Factorio creates them when they are
+    /// missing.
+    pub fn hash_mods(&self) -> Result<String> {
+        let mut hasher = Sha1::new();
+        if self.mods_dir.path().exists() {
+            let mut visited = HashSet::new();
+            hash_dir(self.mods_dir.path(), &mut hasher, &[MOD_LIST, MOD_SETTINGS], &mut visited)?;
         }
+        Ok(hex::encode(hasher.finalize()))
+    }
+}
 
-        fs::create_dir_all(&self.mods_dir)?;
-        Self::copy_or_link(path, self.mods_dir.join(path.file_name().unwrap()))?;
-        Ok(())
+/// Hashes a directory recursively, following the symlinks of linked mods.
+/// Directories that were already visited, e.g. through a symlink loop, are
+/// only hashed once.
+fn hash_dir(
+    dir: &Path,
+    hasher: &mut Sha1,
+    skip: &[&str],
+    visited: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    if !visited.insert(dir.canonicalize()?) {
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name();
+        if skip.iter().any(|skipped| name == *skipped) {
+            continue;
+        }
+
+        let path = entry.path();
+        hasher.write_all(name.as_encoded_bytes())?;
+        if path.is_dir() {
+            hasher.write_all(b"/")?;
+            hash_dir(&path, hasher, &[], visited)?;
+            hasher.write_all(b"\0")?;
+        } else {
+            hasher.write_all(&fs::metadata(&path)?.len().to_le_bytes())?;
+            io::copy(&mut File::open(&path)?, hasher)?;
+        }
     }
+    Ok(())
 }
 
 /// The contents of an `info.json` file in a mod. Described [on the
@@ -72,3 +131,40 @@ pub struct ModManifest {
     #[builder(default)]
     pub dependencies: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::ModController;
+
+    #[test]
+    fn hash_mods() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let controller = ModController::new(dir.path().to_owned());
+        let mod_dir = dir.path().join("mymod_1.0.0");
+        fs::create_dir(&mod_dir)?;
+        fs::write(mod_dir.join("data.lua"), "data:extend({})")?;
+        fs::write(dir.path().join("other_1.0.0.zip"), "PK")?;
+
+        let hash = controller.hash_mods()?;
+        assert_eq!(controller.hash_mods()?, hash);
+
+        // Files that Factorio writes itself don't matter.
+        fs::write(dir.path().join("mod-list.json"), "{}")?;
+        assert_eq!(controller.hash_mods()?, hash);
+
+        // A symlink loop in an unpacked mod is only followed once.
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&mod_dir, mod_dir.join("loop"))?;
+            assert_ne!(controller.hash_mods()?, hash);
+            fs::remove_file(mod_dir.join("loop"))?;
+            assert_eq!(controller.hash_mods()?, hash);
+        }
+
+        fs::write(mod_dir.join("data.lua"), "data:extend({{}})")?;
+        assert_ne!(controller.hash_mods()?, hash);
+        Ok(())
+    }
+}
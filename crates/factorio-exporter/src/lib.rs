@@ -17,7 +17,10 @@
 //!
 //! The result is returned as a [`serde_yaml::Value`] object, which can easily
 //! deserialized of serialized into other data types further. See this [example]
-//! to see the structure that the data has.
+//! to see the structure that the data has. [`FactorioExporter::export_prototypes`]
+//! returns a typed view of the same data, see the [`prototypes`] module.
+//! Translated prototype names and icons are exported separately with
+//! [`FactorioExporter::export_locale`] and [`FactorioExporter::export_icons`].
 //!
 //! [example]:
 //!     https://raw.githubusercontent.com/MForster/factorio-rust-tools/main/crates/factorio-exporter/data/vanilla.json
@@ -26,10 +29,13 @@ use std::path::PathBuf;
 
 use thiserror::Error;
 
-pub use exporter::FactorioExporter;
+pub use exporter::{parse_version_output, FactorioExporter};
 
 mod exporter;
 mod internal;
+pub mod locale;
+pub mod normalize;
+pub mod prototypes;
 
 /// Main result type used throughout factorio-explorer
 pub type Result<T> = std::result::Result<T, FactorioExporterError>;
@@ -67,6 +73,10 @@ pub enum FactorioExporterError {
     #[error("failed to parse JSON")]
     JsonParsingError(#[from] serde_json::Error),
 
+    /// Error that is raised if a mod couldn't be read or installed.
+    #[error("mod handling failed")]
+    ModError(#[from] factorio_mod_api::FactorioModApiError),
+
     /// Error that is raised if deserialization from JSON failed.
     #[error("failed to parse YAML")]
     YamlParsingError(#[from] serde_yaml::Error),
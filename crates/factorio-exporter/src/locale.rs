@@ -0,0 +1,139 @@
+//! Translated names and descriptions of prototypes, as exported by Factorio's
+//! `--dump-prototype-locale` option.
+
+use std::collections::BTreeMap;
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prototypes::{ENTITY_TYPES, ITEM_TYPES};
+
+/// The contents of `prototype-locale.json`, for the locale configured in
+/// [`FactorioExporter::new`](crate::FactorioExporter::new).
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PrototypeLocale {
+    /// Translations by locale section, like `item-name` or
+    /// `entity-description`, and prototype name. The sections are the same as
+    /// in Factorio's `.cfg` locale files.
+    #[serde(flatten)]
+    pub categories: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl PrototypeLocale {
+    /// The translated name of a prototype.
+    pub fn name(&self, prototype_type: &str, name: &str) -> Option<&str> {
+        self.lookup(prototype_type, name, "name")
+    }
+
+    /// The translated description of a prototype.
+    pub fn description(&self, prototype_type: &str, name: &str) -> Option<&str> {
+        self.lookup(prototype_type, name, "description")
+    }
+
+    /// Adds the translations to the result of
+    /// [`FactorioExporter::export`](crate::FactorioExporter::export), as
+    /// `translated_name` and `translated_description` properties of each
+    /// prototype that has them.
+    pub fn apply(&self, prototypes: &mut Value) {
+        let Value::Object(types) = prototypes else {
+            return;
+        };
+
+        for (prototype_type, by_name) in types {
+            let Value::Object(by_name) = by_name else {
+                continue;
+            };
+            for (name, prototype) in by_name {
+                let Value::Object(prototype) = prototype else {
+                    continue;
+                };
+                if let Some(text) = self.name(prototype_type, name) {
+                    prototype.insert("translated_name".into(), text.into());
+                }
+                if let Some(text) = self.description(prototype_type, name) {
+                    prototype.insert("translated_description".into(), text.into());
+                }
+            }
+        }
+    }
+
+    fn lookup(&self, prototype_type: &str, name: &str, kind: &str) -> Option<&str> {
+        let section = format!("{}-{kind}", locale_category(prototype_type));
+        self.categories.get(&section)?.get(name).map(String::as_str)
+    }
+}
+
+/// The locale category of a prototype type. Items, entities, equipment and
+/// space locations share a category across their types.
+fn locale_category(prototype_type: &str) -> &str {
+    if ITEM_TYPES.contains(&prototype_type) {
+        "item"
+    } else if ENTITY_TYPES.contains(&prototype_type) {
+        "entity"
+    } else if prototype_type.ends_with("-equipment") {
+        "equipment"
+    } else if prototype_type == "planet" {
+        "space-location"
+    } else {
+        prototype_type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::PrototypeLocale;
+
+    #[test]
+    fn apply() -> Result<(), Box<dyn std::error::Error>> {
+        let locale: PrototypeLocale =
+            serde_json::from_slice(include_bytes!("tests/prototype-locale.json"))?;
+
+        let mut prototypes = json!({
+            "module": { "speed-module": { "name": "speed-module" } },
+            "container": { "iron-chest": { "name": "iron-chest" } },
+            "assembling-machine": { "assembling-machine-1": { "name": "assembling-machine-1" } },
+            "battery-equipment": { "battery-equipment": { "name": "battery-equipment" } },
+            "planet": { "nauvis": { "name": "nauvis" } },
+            "virtual-signal": { "signal-A": { "name": "signal-A" } },
+            "recipe": { "speed-module": { "name": "speed-module" } }
+        });
+        locale.apply(&mut prototypes);
+
+        assert_eq!(
+            prototypes,
+            json!({
+                "module": {
+                    "speed-module": {
+                        "name": "speed-module",
+                        "translated_name": "Speed module",
+                        "translated_description": "Increases the speed of the machine."
+                    }
+                },
+                "container": {
+                    "iron-chest": { "name": "iron-chest", "translated_name": "Iron chest" }
+                },
+                "assembling-machine": {
+                    "assembling-machine-1": {
+                        "name": "assembling-machine-1",
+                        "translated_name": "Assembling machine 1",
+                        "translated_description": "Crafts items from ingredients."
+                    }
+                },
+                "battery-equipment": {
+                    "battery-equipment": {
+                        "name": "battery-equipment",
+                        "translated_name": "Personal battery"
+                    }
+                },
+                "planet": { "nauvis": { "name": "nauvis", "translated_name": "Nauvis" } },
+                "virtual-signal": {
+                    "signal-A": { "name": "signal-A", "translated_name": "Signal A" }
+                },
+                "recipe": { "speed-module": { "name": "speed-module" } }
+            })
+        );
+        Ok(())
+    }
+}
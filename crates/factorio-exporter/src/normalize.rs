@@ -0,0 +1,370 @@
+//! Normalization of the different ways in which recipes can be defined.
+//!
+//! Recipes in the exported data come in several shapes: a single `result`
+//! with `result_count` or a list of `results`, ingredients and products as
+//! `["iron-ore", 1]` tuples or as full definitions, and separate `normal` and
+//! `expensive` variants before Factorio 2.0. [`NormalizedRecipe`] represents
+//! all of them in one canonical form.
+
+use serde_derive::Serialize;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::{
+    prototypes::{DifficultyRecipe, Ingredient, Product, Recipe, RecipeData},
+    Result,
+};
+
+const DEFAULT_CATEGORY: &str = "crafting";
+const DEFAULT_ENERGY_REQUIRED: f64 = 0.5;
+const ITEM: &str = "item";
+
+/// A recipe in canonical form.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NormalizedRecipe {
+    #[serde(rename = "type")]
+    pub prototype_type: String,
+
+    pub name: String,
+
+    pub category: String,
+
+    /// The recipe in the normal difficulty, or `None` if it isn't available.
+    /// Recipes without difficulty variants have the same definition in both
+    /// difficulties.
+    pub normal: Option<NormalizedRecipeData>,
+
+    /// The recipe in the expensive difficulty, or `None` if it isn't
+    /// available.
+    pub expensive: Option<NormalizedRecipeData>,
+
+    /// All other properties of the recipe.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The definition of a recipe in one difficulty, with all defaults applied.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NormalizedRecipeData {
+    pub ingredients: Vec<NormalizedIngredient>,
+    pub results: Vec<NormalizedProduct>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_product: Option<String>,
+
+    pub energy_required: f64,
+    pub enabled: bool,
+    pub hidden: bool,
+
+    /// Other properties of a difficulty variant.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NormalizedIngredient {
+    /// "item" or "fluid".
+    #[serde(rename = "type")]
+    pub ingredient_type: String,
+
+    pub name: String,
+    pub amount: f64,
+    pub catalyst_amount: f64,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NormalizedProduct {
+    /// "item" or "fluid", or "research-progress" since Factorio 2.0.
+    #[serde(rename = "type")]
+    pub product_type: String,
+
+    pub name: String,
+
+    #[serde(flatten)]
+    pub amount: ProductAmount,
+
+    pub probability: f64,
+
+    /// The part of the amount that isn't affected by productivity bonuses.
+    /// This is `ignored_by_productivity` since Factorio 2.0.
+    pub catalyst_amount: f64,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// The amount of a product: either fixed or random within a range.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ProductAmount {
+    Fixed { amount: f64 },
+    Range { amount_min: f64, amount_max: f64 },
+}
+
+impl Recipe {
+    /// Converts the recipe into canonical form.
+    pub fn normalize(&self) -> NormalizedRecipe {
+        let variant = |variant: &Option<DifficultyRecipe>| match variant {
+            Some(DifficultyRecipe::Data(data)) => Some(Some(normalize_data(data))),
+            Some(DifficultyRecipe::Disabled(_)) => Some(None),
+            None => None,
+        };
+
+        // If only one difficulty is defined, the other one copies it. Without
+        // difficulties, the top-level properties define the recipe. Properties
+        // that aren't part of the recipe data are kept at the top level then.
+        let (normal, expensive) = match (variant(&self.normal), variant(&self.expensive)) {
+            (None, None) => {
+                let data = normalize_data(&RecipeData { extra: Map::new(), ..self.data.clone() });
+                (Some(data.clone()), Some(data))
+            }
+            (Some(normal), None) => (normal.clone(), normal),
+            (None, Some(expensive)) => (expensive.clone(), expensive),
+            (Some(normal), Some(expensive)) => (normal, expensive),
+        };
+
+        NormalizedRecipe {
+            prototype_type: self.prototype_type.clone(),
+            name: self.name.clone(),
+            category: self.category.clone().unwrap_or_else(|| DEFAULT_CATEGORY.into()),
+            normal,
+            expensive,
+            extra: self.data.extra.clone(),
+        }
+    }
+}
+
+fn normalize_data(data: &RecipeData) -> NormalizedRecipeData {
+    let results = match &data.result {
+        Some(result) => vec![NormalizedProduct {
+            product_type: ITEM.into(),
+            name: result.clone(),
+            amount: ProductAmount::Fixed { amount: data.result_count.unwrap_or(1.0) },
+            probability: 1.0,
+            catalyst_amount: 0.0,
+            extra: Map::new(),
+        }],
+        None => data.results.iter().map(normalize_product).collect(),
+    };
+
+    NormalizedRecipeData {
+        ingredients: data.ingredients.iter().map(normalize_ingredient).collect(),
+        results,
+        main_product: data.main_product.clone(),
+        energy_required: data.energy_required.unwrap_or(DEFAULT_ENERGY_REQUIRED),
+        enabled: data.enabled.unwrap_or(true),
+        hidden: data.hidden.unwrap_or(false),
+        extra: data.extra.clone(),
+    }
+}
+
+fn normalize_ingredient(ingredient: &Ingredient) -> NormalizedIngredient {
+    match ingredient {
+        Ingredient::Short(name, amount) => NormalizedIngredient {
+            ingredient_type: ITEM.into(),
+            name: name.clone(),
+            amount: *amount,
+            catalyst_amount: 0.0,
+            extra: Map::new(),
+        },
+        Ingredient::Full(ingredient) => NormalizedIngredient {
+            ingredient_type: ingredient.ingredient_type.clone().unwrap_or_else(|| ITEM.into()),
+            name: ingredient.name.clone(),
+            amount: ingredient.amount,
+            catalyst_amount: ingredient.catalyst_amount.unwrap_or(0.0),
+            extra: ingredient.extra.clone(),
+        },
+    }
+}
+
+fn normalize_product(product: &Product) -> NormalizedProduct {
+    match product {
+        Product::Short(name, amount) => NormalizedProduct {
+            product_type: ITEM.into(),
+            name: name.clone(),
+            amount: ProductAmount::Fixed { amount: *amount },
+            probability: 1.0,
+            catalyst_amount: 0.0,
+            extra: Map::new(),
+        },
+        Product::Full(product) => {
+            let mut extra = product.extra.clone();
+            let ignored_by_productivity =
+                extra.remove("ignored_by_productivity").and_then(|v| v.as_f64());
+
+            let amount = match (product.amount, product.amount_min, product.amount_max) {
+                (Some(amount), _, _) => ProductAmount::Fixed { amount },
+                (None, Some(amount_min), Some(amount_max)) => {
+                    ProductAmount::Range { amount_min, amount_max }
+                }
+                (None, amount_min, amount_max) => {
+                    warn!("product '{}' has no amount", product.name);
+                    ProductAmount::Range {
+                        amount_min: amount_min.or(amount_max).unwrap_or(0.0),
+                        amount_max: amount_max.or(amount_min).unwrap_or(0.0),
+                    }
+                }
+            };
+
+            NormalizedProduct {
+                product_type: product.product_type.clone().unwrap_or_else(|| ITEM.into()),
+                name: product.name.clone(),
+                amount,
+                probability: product.probability.unwrap_or(1.0),
+                catalyst_amount: product.catalyst_amount.or(ignored_by_productivity).unwrap_or(0.0),
+                extra,
+            }
+        }
+    }
+}
+
+/// Replaces all recipes in the result of
+/// [`FactorioExporter::export`](crate::FactorioExporter::export) with their
+/// [`NormalizedRecipe`] form. Recipes that can't be parsed are left unchanged.
+pub fn normalize_recipes(prototypes: &mut Value) -> Result<()> {
+    let Some(Value::Object(recipes)) = prototypes.get_mut("recipe") else {
+        return Ok(());
+    };
+
+    for (name, value) in recipes.iter_mut() {
+        match serde_json::from_value::<Recipe>(value.clone()) {
+            Ok(recipe) => *value = serde_json::to_value(recipe.normalize())?,
+            Err(e) => warn!("not normalizing recipe '{name}': {e}"),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::normalize_recipes;
+
+    fn normalize(recipe: Value) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut prototypes = json!({ "recipe": { "test": recipe } });
+        normalize_recipes(&mut prototypes)?;
+        Ok(prototypes["recipe"]["test"].take())
+    }
+
+    #[test]
+    fn single_result() -> Result<(), Box<dyn std::error::Error>> {
+        let normalized = normalize(json!({
+            "type": "recipe",
+            "name": "iron-plate",
+            "category": "smelting",
+            "energy_required": 3.2,
+            "ingredients": [["iron-ore", 1]],
+            "result": "iron-plate",
+            "order": "a"
+        }))?;
+
+        let data = json!({
+            "ingredients": [{ "type": "item", "name": "iron-ore", "amount": 1.0, "catalyst_amount": 0.0 }],
+            "results": [{
+                "type": "item",
+                "name": "iron-plate",
+                "amount": 1.0,
+                "probability": 1.0,
+                "catalyst_amount": 0.0
+            }],
+            "energy_required": 3.2,
+            "enabled": true,
+            "hidden": false
+        });
+        assert_eq!(
+            normalized,
+            json!({
+                "type": "recipe",
+                "name": "iron-plate",
+                "category": "smelting",
+                "normal": data,
+                "expensive": data,
+                "order": "a"
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn difficulties() -> Result<(), Box<dyn std::error::Error>> {
+        let normalized = normalize(json!({
+            "type": "recipe",
+            "name": "iron-gear-wheel",
+            "normal": { "ingredients": [["iron-plate", 2]], "result": "iron-gear-wheel" },
+            "expensive": false
+        }))?;
+
+        assert_eq!(normalized["category"], "crafting");
+        assert_eq!(normalized["normal"]["ingredients"][0]["amount"], 2.0);
+        assert_eq!(normalized["normal"]["energy_required"], 0.5);
+        assert_eq!(normalized["expensive"], Value::Null);
+
+        let normalized = normalize(json!({
+            "type": "recipe",
+            "name": "iron-gear-wheel",
+            "normal": { "ingredients": [["iron-plate", 2]], "result": "iron-gear-wheel" }
+        }))?;
+        assert_eq!(normalized["expensive"], normalized["normal"]);
+        Ok(())
+    }
+
+    #[test]
+    fn products() -> Result<(), Box<dyn std::error::Error>> {
+        let normalized = normalize(json!({
+            "type": "recipe",
+            "name": "kovarex",
+            "category": "chemistry",
+            "ingredients": [{ "type": "fluid", "name": "water", "amount": 10, "fluidbox_index": 1 }],
+            "results": [
+                { "name": "uranium-235", "amount": 41, "catalyst_amount": 40 },
+                { "type": "item", "name": "uranium-238", "amount_min": 1, "amount_max": 3, "probability": 0.5 },
+                { "type": "fluid", "name": "steam", "amount": 5, "ignored_by_productivity": 5, "temperature": 165 }
+            ]
+        }))?;
+
+        let data = &normalized["normal"];
+        assert_eq!(
+            data["ingredients"][0],
+            json!({
+                "type": "fluid",
+                "name": "water",
+                "amount": 10.0,
+                "catalyst_amount": 0.0,
+                "fluidbox_index": 1
+            })
+        );
+        assert_eq!(
+            data["results"],
+            json!([
+                {
+                    "type": "item",
+                    "name": "uranium-235",
+                    "amount": 41.0,
+                    "probability": 1.0,
+                    "catalyst_amount": 40.0
+                },
+                {
+                    "type": "item",
+                    "name": "uranium-238",
+                    "amount_min": 1.0,
+                    "amount_max": 3.0,
+                    "probability": 0.5,
+                    "catalyst_amount": 0.0
+                },
+                {
+                    "type": "fluid",
+                    "name": "steam",
+                    "amount": 5.0,
+                    "probability": 1.0,
+                    "catalyst_amount": 5.0,
+                    "temperature": 165
+                }
+            ])
+        );
+        Ok(())
+    }
+}
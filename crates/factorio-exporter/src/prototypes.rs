@@ -0,0 +1,664 @@
+//! A typed view of the prototype definitions that
+//! [`FactorioExporter::export`](crate::FactorioExporter::export) returns.
+//!
+//! Only the most commonly used properties are typed. All other properties,
+//! including the ones that mods add, are preserved in the `extra` map of each
+//! prototype. Prototypes that don't match the expected structure are kept
+//! untyped in [`Prototypes::other`], so that unusual mod data never fails to
+//! parse.
+
+use std::collections::BTreeMap;
+
+use serde::{de::DeserializeOwned, Deserializer};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use crate::{FactorioExporterError, Result};
+
+/// Prototype types that describe items: `item` and its specialized variants.
+pub const ITEM_TYPES: &[&str] = &[
+    "ammo",
+    "armor",
+    "blueprint",
+    "blueprint-book",
+    "capsule",
+    "copy-paste-tool",
+    "deconstruction-item",
+    "gun",
+    "item",
+    "item-with-entity-data",
+    "item-with-inventory",
+    "item-with-label",
+    "item-with-tags",
+    "mining-tool",
+    "module",
+    "rail-planner",
+    "repair-tool",
+    "selection-tool",
+    "space-platform-starter-pack",
+    "spidertron-remote",
+    "tool",
+    "upgrade-item",
+];
+
+/// Prototype types that describe entities.
+pub const ENTITY_TYPES: &[&str] = &[
+    "accumulator",
+    "agricultural-tower",
+    "ammo-turret",
+    "arithmetic-combinator",
+    "arrow",
+    "artillery-flare",
+    "artillery-projectile",
+    "artillery-turret",
+    "artillery-wagon",
+    "assembling-machine",
+    "asteroid",
+    "asteroid-collector",
+    "beacon",
+    "beam",
+    "boiler",
+    "burner-generator",
+    "capture-robot",
+    "car",
+    "cargo-bay",
+    "cargo-landing-pad",
+    "cargo-pod",
+    "cargo-wagon",
+    "character",
+    "character-corpse",
+    "cliff",
+    "combat-robot",
+    "constant-combinator",
+    "construction-robot",
+    "container",
+    "corpse",
+    "curved-rail",
+    "curved-rail-a",
+    "curved-rail-b",
+    "decider-combinator",
+    "deconstructible-tile-proxy",
+    "display-panel",
+    "electric-energy-interface",
+    "electric-pole",
+    "electric-turret",
+    "elevated-curved-rail-a",
+    "elevated-curved-rail-b",
+    "elevated-half-diagonal-rail",
+    "elevated-straight-rail",
+    "entity-ghost",
+    "explosion",
+    "fire",
+    "fish",
+    "fluid-turret",
+    "fluid-wagon",
+    "flying-text",
+    "furnace",
+    "fusion-generator",
+    "fusion-reactor",
+    "gate",
+    "generator",
+    "half-diagonal-rail",
+    "heat-interface",
+    "heat-pipe",
+    "highlight-box",
+    "infinity-container",
+    "infinity-pipe",
+    "inserter",
+    "item-entity",
+    "item-request-proxy",
+    "lab",
+    "lamp",
+    "land-mine",
+    "lane-splitter",
+    "leaf-particle",
+    "legacy-curved-rail",
+    "legacy-straight-rail",
+    "lightning",
+    "lightning-attractor",
+    "linked-belt",
+    "linked-container",
+    "loader",
+    "loader-1x1",
+    "locomotive",
+    "logistic-container",
+    "logistic-robot",
+    "market",
+    "mining-drill",
+    "offshore-pump",
+    "particle",
+    "particle-source",
+    "pipe",
+    "pipe-to-ground",
+    "plant",
+    "player-port",
+    "power-switch",
+    "programmable-speaker",
+    "projectile",
+    "proxy-container",
+    "pump",
+    "radar",
+    "rail-chain-signal",
+    "rail-ramp",
+    "rail-remnants",
+    "rail-signal",
+    "rail-support",
+    "reactor",
+    "resource",
+    "roboport",
+    "rocket-silo",
+    "rocket-silo-rocket",
+    "rocket-silo-rocket-shadow",
+    "segment",
+    "segmented-unit",
+    "selector-combinator",
+    "simple-entity",
+    "simple-entity-with-force",
+    "simple-entity-with-owner",
+    "smoke",
+    "smoke-with-trigger",
+    "solar-panel",
+    "space-platform-hub",
+    "speech-bubble",
+    "spider-leg",
+    "spider-unit",
+    "spider-vehicle",
+    "splitter",
+    "sticker",
+    "storage-tank",
+    "straight-rail",
+    "stream",
+    "temporary-container",
+    "thruster",
+    "tile-ghost",
+    "train-stop",
+    "transport-belt",
+    "tree",
+    "turret",
+    "underground-belt",
+    "unit",
+    "unit-spawner",
+    "valve",
+    "wall",
+];
+
+/// All prototypes of an export, grouped by kind and name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Prototypes {
+    pub recipes: BTreeMap<String, Recipe>,
+
+    /// Items of all item types, like `item`, `ammo` or `module`. Item names
+    /// are unique across these types.
+    pub items: BTreeMap<String, Item>,
+
+    pub fluids: BTreeMap<String, Fluid>,
+    pub technologies: BTreeMap<String, Technology>,
+
+    /// Entities by category, i.e. prototype type, and name.
+    pub entities: BTreeMap<String, BTreeMap<String, Entity>>,
+
+    /// All other prototypes, by prototype type and name, as they were
+    /// exported.
+    pub other: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl Prototypes {
+    /// Converts the result of
+    /// [`FactorioExporter::export`](crate::FactorioExporter::export).
+    pub fn from_value(value: Value) -> Result<Prototypes> {
+        let Value::Object(types) = value else {
+            return Err(FactorioExporterError::FactorioOutputError {
+                message: "expected an object with prototype types".into(),
+                output: value.to_string(),
+            });
+        };
+
+        let mut prototypes = Prototypes::default();
+        for (prototype_type, by_name) in types {
+            // Empty Lua tables are exported as empty objects, so anything that
+            // isn't an object can only be a non-prototype value.
+            let Value::Object(by_name) = by_name else {
+                continue;
+            };
+            for (name, value) in by_name {
+                prototypes.insert(&prototype_type, name, value);
+            }
+        }
+        Ok(prototypes)
+    }
+
+    /// Looks up an entity by name, in all categories.
+    pub fn entity(&self, name: &str) -> Option<&Entity> {
+        self.entities.values().find_map(|by_name| by_name.get(name))
+    }
+
+    fn insert(&mut self, prototype_type: &str, name: String, value: Value) {
+        let untyped = match prototype_type {
+            "recipe" => insert_typed(&mut self.recipes, name, value),
+            "fluid" => insert_typed(&mut self.fluids, name, value),
+            "technology" => insert_typed(&mut self.technologies, name, value),
+            t if ITEM_TYPES.contains(&t) => insert_typed(&mut self.items, name, value),
+            t if ENTITY_TYPES.contains(&t) => {
+                insert_typed(self.entities.entry(t.into()).or_default(), name, value)
+            }
+            _ => Some((name, value)),
+        };
+
+        if let Some((name, value)) = untyped {
+            self.other.entry(prototype_type.into()).or_default().insert(name, value);
+        }
+    }
+}
+
+/// Inserts a typed prototype into `map`. Returns the prototype back if it
+/// doesn't have the expected structure.
+fn insert_typed<T: DeserializeOwned>(
+    map: &mut BTreeMap<String, T>,
+    name: String,
+    value: Value,
+) -> Option<(String, Value)> {
+    match T::deserialize(&value) {
+        Ok(prototype) => {
+            map.insert(name, prototype);
+            None
+        }
+        Err(e) => {
+            warn!("keeping prototype '{name}' untyped: {e}");
+            Some((name, value))
+        }
+    }
+}
+
+/// A recipe, as defined in the data stage.
+///
+/// Before Factorio 2.0, recipes can define separate variants for the normal
+/// and expensive difficulty. The fields in `data` are empty in this case.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Recipe {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub prototype_type: String,
+
+    /// Defaults to "crafting".
+    pub category: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub normal: Option<DifficultyRecipe>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expensive: Option<DifficultyRecipe>,
+
+    #[serde(flatten)]
+    pub data: RecipeData,
+}
+
+/// The normal or expensive variant of a recipe.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum DifficultyRecipe {
+    Data(Box<RecipeData>),
+
+    /// `false` if the recipe isn't available in this difficulty.
+    Disabled(bool),
+}
+
+/// The ingredients, products and crafting properties of a recipe.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct RecipeData {
+    #[serde(default, deserialize_with = "lua_list")]
+    pub ingredients: Vec<Ingredient>,
+
+    /// The single product of the recipe, if `results` isn't used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_count: Option<f64>,
+
+    #[serde(default, deserialize_with = "lua_list")]
+    pub results: Vec<Product>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_product: Option<String>,
+
+    /// Crafting time in seconds. Defaults to 0.5.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy_required: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An ingredient of a recipe or technology, either in the short form
+/// `["iron-plate", 2]` or as a full definition.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Ingredient {
+    Short(String, f64),
+    Full(IngredientDefinition),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct IngredientDefinition {
+    /// "item" or "fluid". Defaults to "item".
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ingredient_type: Option<String>,
+
+    pub name: String,
+    pub amount: f64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catalyst_amount: Option<f64>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A product of a recipe or a mining result, either in the short form
+/// `["iron-plate", 2]` or as a full definition.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Product {
+    Short(String, f64),
+    Full(ProductDefinition),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ProductDefinition {
+    /// "item" or "fluid". Defaults to "item".
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub product_type: Option<String>,
+
+    pub name: String,
+
+    /// The amount. If it's missing, the amount is random between
+    /// `amount_min` and `amount_max`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_min: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_max: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probability: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub catalyst_amount: Option<f64>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Item {
+    pub name: String,
+
+    /// The item type, e.g. "item" or "module".
+    #[serde(rename = "type")]
+    pub prototype_type: String,
+
+    pub stack_size: f64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subgroup: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+
+    /// The entity that is built from the item.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub place_result: Option<String>,
+
+    /// Energy as a string with unit, e.g. "4MJ".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuel_value: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuel_category: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Fluid {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub prototype_type: String,
+
+    pub default_temperature: f64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_temperature: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subgroup: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuel_value: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Technology {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub prototype_type: String,
+
+    /// The research costs. Technologies that are researched by a trigger
+    /// don't have a unit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<TechnologyUnit>,
+
+    #[serde(default, deserialize_with = "lua_list")]
+    pub prerequisites: Vec<String>,
+
+    #[serde(default, deserialize_with = "lua_list")]
+    pub effects: Vec<TechnologyEffect>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TechnologyUnit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<f64>,
+
+    /// Formula for the count of infinite technologies, e.g. "2^(L-6)*1000".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count_formula: Option<String>,
+
+    /// Time per unit, in ticks.
+    pub time: f64,
+
+    #[serde(default, deserialize_with = "lua_list")]
+    pub ingredients: Vec<Ingredient>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TechnologyEffect {
+    /// The effect type, e.g. "unlock-recipe".
+    #[serde(rename = "type")]
+    pub effect_type: String,
+
+    /// The unlocked recipe, for "unlock-recipe" effects.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipe: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Entity {
+    pub name: String,
+
+    /// The entity category, e.g. "assembling-machine".
+    #[serde(rename = "type")]
+    pub prototype_type: String,
+
+    #[serde(default, deserialize_with = "lua_list")]
+    pub flags: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minable: Option<Minable>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_health: Option<f64>,
+
+    /// The crafting speed of crafting machines.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crafting_speed: Option<f64>,
+
+    #[serde(default, deserialize_with = "lua_list")]
+    pub crafting_categories: Vec<String>,
+
+    /// Energy as a string with unit, e.g. "150kW".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub energy_usage: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Minable {
+    pub mining_time: f64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<f64>,
+
+    #[serde(default, deserialize_with = "lua_list")]
+    pub results: Vec<Product>,
+
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Deserializes a list that may have been exported as an object. Factorio
+/// can't distinguish empty Lua arrays from empty dictionaries, and exports
+/// sparse arrays with numeric keys.
+fn lua_list<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LuaList<T> {
+        Array(Vec<T>),
+        Object(BTreeMap<String, T>),
+    }
+
+    Ok(match <LuaList<T> as serde::Deserialize>::deserialize(deserializer)? {
+        LuaList::Array(items) => items,
+        LuaList::Object(entries) => {
+            let mut entries: Vec<_> = entries.into_iter().collect();
+            entries.sort_by_key(|(key, _)| key.parse::<usize>().unwrap_or(usize::MAX));
+            entries.into_iter().map(|(_, item)| item).collect()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{DifficultyRecipe, Ingredient, Product, Prototypes};
+
+    #[test]
+    fn typed_prototypes() -> Result<(), Box<dyn std::error::Error>> {
+        let prototypes = Prototypes::from_value(json!({
+            "recipe": {
+                "iron-plate": {
+                    "type": "recipe",
+                    "name": "iron-plate",
+                    "category": "smelting",
+                    "energy_required": 3.2,
+                    "ingredients": [["iron-ore", 1]],
+                    "result": "iron-plate"
+                },
+                "iron-gear-wheel": {
+                    "type": "recipe",
+                    "name": "iron-gear-wheel",
+                    "normal": { "ingredients": [["iron-plate", 2]], "result": "iron-gear-wheel" },
+                    "expensive": false,
+                    "mymod_flag": true
+                }
+            },
+            "module": {
+                "speed-module": { "type": "module", "name": "speed-module", "stack_size": 50 }
+            },
+            "assembling-machine": {
+                "assembling-machine-1": {
+                    "type": "assembling-machine",
+                    "name": "assembling-machine-1",
+                    "crafting_speed": 0.5,
+                    "crafting_categories": ["crafting"],
+                    "minable": { "mining_time": 0.2, "result": "assembling-machine-1" },
+                    "flags": {}
+                }
+            },
+            "fluid": {
+                "broken": { "type": "fluid", "name": "broken", "default_temperature": "hot" }
+            },
+            "recipe-category": {
+                "smelting": { "type": "recipe-category", "name": "smelting" }
+            }
+        }))?;
+
+        let plate = &prototypes.recipes["iron-plate"];
+        assert_eq!(plate.category.as_deref(), Some("smelting"));
+        assert_eq!(plate.data.ingredients, [Ingredient::Short("iron-ore".into(), 1.0)]);
+        assert_eq!(plate.data.result.as_deref(), Some("iron-plate"));
+
+        let gear = &prototypes.recipes["iron-gear-wheel"];
+        let Some(DifficultyRecipe::Data(normal)) = &gear.normal else {
+            panic!("missing normal recipe: {gear:?}");
+        };
+        assert_eq!(normal.ingredients, [Ingredient::Short("iron-plate".into(), 2.0)]);
+        assert_eq!(gear.expensive, Some(DifficultyRecipe::Disabled(false)));
+        assert_eq!(gear.data.extra["mymod_flag"], true);
+        assert_eq!(gear.data.results, Vec::<Product>::new());
+
+        assert_eq!(prototypes.items["speed-module"].stack_size, 50.0);
+
+        let machine = prototypes.entity("assembling-machine-1").unwrap();
+        assert_eq!(machine.crafting_speed, Some(0.5));
+        assert!(machine.flags.is_empty());
+        assert_eq!(
+            machine.minable.as_ref().unwrap().result.as_deref(),
+            Some("assembling-machine-1")
+        );
+
+        assert!(prototypes.fluids.is_empty());
+        assert!(prototypes.other["fluid"].contains_key("broken"));
+        assert!(prototypes.other["recipe-category"].contains_key("smelting"));
+        Ok(())
+    }
+}
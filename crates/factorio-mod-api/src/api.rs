@@ -12,21 +12,156 @@ use url::Url;
 
 use crate::{FactorioModApiError, Result};
 
-/// A mod as returned from the `https://mods.factorio.com/api/mods` endpoint
-/// (not yet implemented).
-#[derive(Debug, Deserialize)]
+/// A mod as returned from the `https://mods.factorio.com/api/mods` endpoint.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ModListing {
     /// Metadata shared between the three different API invocations.
     #[serde(flatten)]
     pub metadata: ModMetadata,
 
-    /// The latest version of the mod available for download.
-    pub latest_release: ModRelease,
+    /// The latest version of the mod available for download. Absent when the
+    /// `namelist` parameter is used.
+    pub latest_release: Option<ModRelease>,
+
+    /// A list of different versions of the mod available for download. Only
+    /// present when the `namelist` parameter is used.
+    pub releases: Option<Vec<ModRelease>>,
+
+    // Undocumented
+    pub score: Option<NotNan<f64>>,
+    pub thumbnail: Option<String>,
 }
 
-/// A mod as returned from the `https://mods.factorio.com/api/mods/{name}/full`
+/// One page of results as returned from the `https://mods.factorio.com/api/mods`
 /// endpoint.
 #[derive(Debug, Deserialize)]
+pub struct ModListResponse {
+    /// Information about the current page. Absent if all results were
+    /// returned at once (`page_size=max`).
+    pub pagination: Option<Pagination>,
+
+    /// The mods on this page.
+    pub results: Vec<ModListing>,
+}
+
+/// Pagination information of a [`ModListResponse`].
+#[derive(Debug, Deserialize)]
+pub struct Pagination {
+    /// Total number of mods that match the query.
+    pub count: u64,
+
+    /// Links to other pages of the result.
+    pub links: PaginationLinks,
+
+    /// The number of the current page, starting at 1.
+    pub page: u64,
+
+    /// The total number of pages.
+    pub page_count: u64,
+
+    /// The number of results per page.
+    pub page_size: u64,
+}
+
+/// Links to the neighbouring pages of a [`ModListResponse`]. Absent links are
+/// `null` in the API response.
+#[derive(Debug, Deserialize)]
+pub struct PaginationLinks {
+    pub first: Option<String>,
+    pub prev: Option<String>,
+    pub next: Option<String>,
+    pub last: Option<String>,
+}
+
+/// Query parameters for [`ModPortalClient::list_mods`].
+///
+/// [`ModPortalClient::list_mods`]: crate::ModPortalClient::list_mods
+#[derive(Clone, Debug, Default)]
+pub struct ModListQuery {
+    /// The first page to request, starting at 1.
+    pub page: Option<u64>,
+
+    /// The number of results per page.
+    pub page_size: Option<PageSize>,
+
+    /// The field to sort the results by.
+    pub sort: Option<ModSortField>,
+
+    /// The order in which results are sorted.
+    pub sort_order: Option<SortOrder>,
+
+    /// Only return mods with these names.
+    pub namelist: Vec<String>,
+
+    /// Only return mods compatible with this Factorio version.
+    pub version: Option<FactorioVersion>,
+}
+
+impl ModListQuery {
+    /// Converts the query into URL query parameters.
+    pub(crate) fn to_query_pairs(&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+        if let Some(page) = self.page {
+            pairs.push(("page", page.to_string()));
+        }
+        if let Some(page_size) = &self.page_size {
+            pairs.push(("page_size", page_size.to_string()));
+        }
+        if let Some(sort) = &self.sort {
+            pairs.push(("sort", sort.to_string()));
+        }
+        if let Some(sort_order) = &self.sort_order {
+            pairs.push(("sort_order", sort_order.to_string()));
+        }
+        if !self.namelist.is_empty() {
+            pairs.push(("namelist", self.namelist.join(",")));
+        }
+        if let Some(version) = &self.version {
+            pairs.push(("version", version.to_string()));
+        }
+        pairs
+    }
+}
+
+/// The number of results per page of a mod listing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PageSize {
+    /// A fixed number of results per page.
+    Count(u64),
+
+    /// Return all results on a single page.
+    Max,
+}
+
+impl Display for PageSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PageSize::Count(count) => write!(f, "{count}"),
+            PageSize::Max => write!(f, "max"),
+        }
+    }
+}
+
+/// Fields that a mod listing can be sorted by.
+#[derive(Clone, Debug, Display, EnumString, Eq, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+pub enum ModSortField {
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Sort order of a mod listing.
+#[derive(Clone, Debug, Display, EnumString, Eq, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A mod as returned from the `https://mods.factorio.com/api/mods/{name}/full`
+/// endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct FullModSpec {
     /// Spec data shared with short spec request.
     #[serde(flatten)]
@@ -50,7 +185,7 @@ pub struct FullModSpec {
 }
 
 /// Mod metadata shared between the three different API invocations.
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ModMetadata {
     /// The mod's machine-readable ID string.
     pub name: String,
@@ -72,8 +207,8 @@ pub struct ModMetadata {
 }
 
 /// A mod as returned from the `https://mods.factorio.com/api/mods/{name}`
-/// endpoint (not yet implemented). Also returned as part of the full request.
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+/// endpoint. Also returned as part of the full request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ModSpec {
     /// Metadata shared between the three different API invocations.
     #[serde(flatten)]
@@ -98,20 +233,20 @@ pub struct ModSpec {
 }
 
 /// A tag object that categorizes a mod.
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ModTag {
     /// An all lower-case string used to identify this tag internally.
     pub name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ModImage {
     pub id: String,
     pub thumbnail: String,
     pub url: Url,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ModLicense {
     pub id: String,
     pub name: String,
@@ -120,7 +255,7 @@ pub struct ModLicense {
     pub url: Url,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ModRelease {
     /// Path to download for a mod. starts with "/download" and does not include
     /// a full url.
@@ -161,7 +296,7 @@ impl<'de> Visitor<'de> for VersionVisitor {
     }
 }
 
-fn parse_version<'de, D>(d: D) -> std::result::Result<Version, D::Error>
+pub(crate) fn parse_version<'de, D>(d: D) -> std::result::Result<Version, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -170,17 +305,81 @@ where
 
 /// Partial contents of the `info.json` file that describes a mod.
 /// <https://wiki.factorio.com/Tutorial:Mod_structure#info.json>
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ModManifest {
-    pub factorio_version: String, // Doesn't parse as semver::Version (no patch level).
+    /// The major Factorio version the mod is made for.
+    pub factorio_version: FactorioVersion,
 
     /// The mod's dependencies. Only available in "full" API calls.
     pub dependencies: Option<Vec<ModDependency>>,
 }
 
+/// A major version of Factorio, e.g. "1.1". Mods declare the version they are
+/// made for in the `factorio_version` field of their `info.json`, which has no
+/// patch level.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FactorioVersion {
+    pub major: u64,
+    pub minor: u64,
+}
+
+impl FactorioVersion {
+    pub const fn new(major: u64, minor: u64) -> FactorioVersion {
+        FactorioVersion { major, minor }
+    }
+
+    /// Whether a mod made for this version can be loaded by the game version
+    /// `game`. Versions need to match exactly, except that Factorio 1.0 also
+    /// loads mods made for 0.18.
+    pub fn is_compatible_with(&self, game: &FactorioVersion) -> bool {
+        self == game
+            || (*self == FactorioVersion::new(0, 18) && *game == FactorioVersion::new(1, 0))
+    }
+}
+
+impl From<&Version> for FactorioVersion {
+    fn from(version: &Version) -> Self {
+        FactorioVersion::new(version.major, version.minor)
+    }
+}
+
+impl FromStr for FactorioVersion {
+    type Err = FactorioModApiError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || FactorioModApiError::InvalidFactorioVersion { version: s.into() };
+        let (major, minor) = s.trim().split_once('.').ok_or_else(invalid)?;
+        Ok(FactorioVersion::new(
+            major.parse().map_err(|_| invalid())?,
+            minor.parse().map_err(|_| invalid())?,
+        ))
+    }
+}
+
+impl TryFrom<String> for FactorioVersion {
+    type Error = FactorioModApiError;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<FactorioVersion> for String {
+    fn from(value: FactorioVersion) -> Self {
+        value.to_string()
+    }
+}
+
+impl Display for FactorioVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
 /// A dependency specification between mods.
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
-#[serde(try_from = "&str")]
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
 pub struct ModDependency {
     pub flavor: ModDependencyFlavor,
     pub name: String,
@@ -240,6 +439,20 @@ impl TryFrom<&str> for ModDependency {
     }
 }
 
+impl TryFrom<String> for ModDependency {
+    type Error = FactorioModApiError;
+
+    fn try_from(value: String) -> Result<Self> {
+        ModDependency::try_from(value.as_str())
+    }
+}
+
+impl From<ModDependency> for String {
+    fn from(value: ModDependency) -> Self {
+        value.to_string()
+    }
+}
+
 impl Display for ModDependency {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.flavor)?;
@@ -303,6 +516,180 @@ pub enum LoginResponse {
     },
 }
 
+/// An API key for the mod portal's publishing APIs. API keys can be created
+/// on the [Factorio profile page](https://factorio.com/profile) and need the
+/// permissions of the respective APIs.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ApiKey(pub String);
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ApiKey(<redacted>)")
+    }
+}
+
+/// Response from one of the publishing endpoints.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PublishResponse<T> {
+    Error { error: PublishErrorKind, message: Option<String> },
+    Success(T),
+}
+
+/// Error codes returned by the publishing endpoints.
+/// <https://wiki.factorio.com/Mod_publish_API#Possible_Errors>
+#[derive(Clone, Debug, Deserialize, Display, EnumString, Eq, PartialEq)]
+#[serde(from = "String")]
+pub enum PublishErrorKind {
+    /// Missing or invalid API key for the current endpoint.
+    InvalidApiKey,
+
+    /// Invalid request.
+    InvalidRequest,
+
+    /// Internal error, please try again later.
+    InternalError,
+
+    /// Insufficient permission for current endpoint.
+    Forbidden,
+
+    /// Unknown error, please try again later.
+    Unknown,
+
+    /// Invalid release data in `info.json`.
+    InvalidModRelease,
+
+    /// Invalid mod data in zipfile.
+    InvalidModUpload,
+
+    /// Mod does not exist in mod portal.
+    UnknownMod,
+
+    /// An error code that isn't documented yet.
+    #[strum(default)]
+    Other(String),
+}
+
+impl From<String> for PublishErrorKind {
+    fn from(value: String) -> Self {
+        PublishErrorKind::from_str(&value).expect("unknown values are parsed as `Other`")
+    }
+}
+
+/// Successful response from the `/api/v2/mods/releases/init_upload` endpoint.
+#[derive(Debug, Deserialize)]
+pub struct InitUploadResponse {
+    /// URL that the mod archive needs to be uploaded to.
+    pub upload_url: Url,
+}
+
+/// Successful response from an upload or edit request.
+#[derive(Debug, Deserialize)]
+pub struct SuccessResponse {
+    pub success: bool,
+
+    /// Path of the mod's page, only returned by some endpoints.
+    pub url: Option<String>,
+}
+
+/// The initial details of a mod that is published for the first time with
+/// [`ModPortalClient::publish_new_mod`]. Title, summary and version are taken
+/// from the mod's `info.json`.
+///
+/// [`ModPortalClient::publish_new_mod`]: crate::ModPortalClient::publish_new_mod
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct NewModDetails {
+    /// A longer description of the mod, in Markdown format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// See [`ModMetadata::category`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    /// The id of the license. See [`ModLicense::id`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<Url>,
+}
+
+/// Successful response from the `/api/v2/mods/images/edit` endpoint.
+#[derive(Debug, Deserialize)]
+pub struct EditImagesResponse {
+    pub success: bool,
+
+    /// The images of the mod after the edit.
+    pub images: Vec<ModImage>,
+}
+
+/// Changes to the details of a mod, as sent to the
+/// `/api/v2/mods/edit_details` endpoint. Fields that are `None` are left
+/// unchanged.
+///
+/// Use `ModDetails::from(&full_spec)` to start from the current details of a
+/// mod.
+///
+/// [`FullModSpec`] can't be used as the request body directly: the endpoint
+/// expects only the editable fields, all of them optional, and refers to the
+/// license and tags by their ids instead of the full [`ModLicense`] and
+/// [`ModTag`] objects.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ModDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// See [`ModMetadata::category`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    /// A list of tag names. See [`ModTag::name`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+
+    /// The id of the license. See [`ModLicense::id`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<Url>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faq: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+}
+
+impl From<&FullModSpec> for ModDetails {
+    fn from(spec: &FullModSpec) -> Self {
+        let short_spec = &spec.short_spec;
+        ModDetails {
+            title: Some(short_spec.metadata.title.clone()),
+            summary: Some(short_spec.metadata.summary.clone()),
+            description: short_spec.description.clone(),
+            category: short_spec.metadata.category.clone(),
+            tags: short_spec.tag.as_ref().map(|tag| vec![tag.name.clone()]),
+            license: Some(spec.license.id.clone()),
+            homepage: Some(spec.homepage.clone()),
+            source_url: spec.source_url.clone(),
+            faq: spec.faq.clone(),
+            deprecated: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -321,4 +708,43 @@ mod test {
         );
         Ok(())
     }
+
+    #[test]
+    fn factorio_version() -> Result<()> {
+        let v: FactorioVersion = serde_json::from_str(r#""1.1""#)?;
+        assert_eq!(v, FactorioVersion::new(1, 1));
+        assert_eq!(serde_json::to_string(&v)?, r#""1.1""#);
+        assert!("1".parse::<FactorioVersion>().is_err());
+
+        assert!(v.is_compatible_with(&FactorioVersion::new(1, 1)));
+        assert!(!v.is_compatible_with(&FactorioVersion::new(2, 0)));
+        assert!(FactorioVersion::new(0, 18).is_compatible_with(&FactorioVersion::new(1, 0)));
+        assert!(!FactorioVersion::new(0, 17).is_compatible_with(&FactorioVersion::new(1, 0)));
+        Ok(())
+    }
+
+    #[test]
+    fn publish_error_kind() -> Result<()> {
+        let known: PublishResponse<SuccessResponse> =
+            serde_json::from_str(r#"{"error": "UnknownMod", "message": "Mod not found"}"#)?;
+        assert!(matches!(
+            known,
+            PublishResponse::Error { error: PublishErrorKind::UnknownMod, .. }
+        ));
+
+        let other: PublishResponse<SuccessResponse> =
+            serde_json::from_str(r#"{"error": "SomethingNew"}"#)?;
+        assert!(matches!(
+            other,
+            PublishResponse::Error { error: PublishErrorKind::Other(e), message: None } if e == "SomethingNew"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_dependency() -> Result<()> {
+        let d = ModDependency::try_from("? some-mod >= 4.2.0")?;
+        assert_eq!(serde_json::to_string(&d)?, r#""? some-mod >= 4.2.0""#);
+        Ok(())
+    }
 }
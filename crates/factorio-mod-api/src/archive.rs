@@ -0,0 +1,153 @@
+//! Access to the contents of mod archives (`.zip` files).
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use semver::Version;
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::{
+    api::{parse_version, FactorioVersion, ModDependency},
+    FactorioModApiError, Result,
+};
+
+/// The contents of the `info.json` file that describes a mod.
+/// <https://wiki.factorio.com/Tutorial:Mod_structure#info.json>
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ModInfo {
+    /// The mod's machine-readable ID string.
+    pub name: String,
+
+    /// The version of the mod.
+    #[serde(deserialize_with = "parse_version")]
+    pub version: Version,
+
+    /// The mod's human-readable name.
+    pub title: String,
+
+    /// The mod's author.
+    pub author: String,
+
+    pub contact: Option<String>,
+    pub homepage: Option<String>,
+    pub description: Option<String>,
+
+    /// The major Factorio version the mod is made for, e.g. "1.1".
+    #[serde(default = "default_factorio_version")]
+    pub factorio_version: FactorioVersion,
+
+    /// The mod's dependencies. Factorio assumes a dependency on `base` if the
+    /// field is missing.
+    #[serde(default = "default_dependencies")]
+    pub dependencies: Vec<ModDependency>,
+}
+
+fn default_factorio_version() -> FactorioVersion {
+    FactorioVersion::new(0, 12)
+}
+
+fn default_dependencies() -> Vec<ModDependency> {
+    vec![ModDependency::unversioned("base".into())]
+}
+
+/// Reads the `info.json` file from a mod archive or an unpacked mod directory.
+/// In archives, Factorio expects it either at the root or inside a single
+/// top-level directory.
+pub fn read_info_json(path: &Path) -> Result<ModInfo> {
+    if path.is_dir() {
+        let info_json = path.join("info.json");
+        if !info_json.is_file() {
+            return Err(FactorioModApiError::InvalidModArchive {
+                path: path.into(),
+                reason: "no info.json found".into(),
+            });
+        }
+        return Ok(serde_json::from_reader(BufReader::new(File::open(info_json)?))?);
+    }
+
+    let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+
+    let info_json = archive
+        .file_names()
+        .filter(|name| {
+            let mut components = name.split('/');
+            matches!(
+                (components.next(), components.next(), components.next()),
+                (Some("info.json"), None, _) | (Some(_), Some("info.json"), None)
+            )
+        })
+        .min_by_key(|name| name.len())
+        .map(String::from)
+        .ok_or_else(|| FactorioModApiError::InvalidModArchive {
+            path: path.into(),
+            reason: "no info.json found".into(),
+        })?;
+
+    let info = serde_json::from_reader(archive.by_name(&info_json)?)?;
+    Ok(info)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{error::Error, fs::File, io::Write, path::Path};
+
+    use semver::Version;
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::read_info_json;
+    use crate::api::{FactorioVersion, ModDependency};
+
+    /// Writes a minimal mod archive with the given `info.json` contents.
+    pub fn write_mod_archive(path: &Path, info_json: &str) -> Result<(), Box<dyn Error>> {
+        let mut zip = ZipWriter::new(File::create(path)?);
+        zip.start_file("mymod_1.2.3/info.json", SimpleFileOptions::default())?;
+        zip.write_all(info_json.as_bytes())?;
+        zip.start_file("mymod_1.2.3/data.lua", SimpleFileOptions::default())?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    #[test]
+    fn info_json() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("mymod_1.2.3.zip");
+        write_mod_archive(
+            &path,
+            r#"{
+                "name": "mymod",
+                "version": "1.2.03",
+                "title": "My Mod",
+                "author": "someone",
+                "factorio_version": "1.1",
+                "dependencies": ["base >= 1.1.0", "? othermod"]
+            }"#,
+        )?;
+
+        let info = read_info_json(&path)?;
+
+        assert_eq!(info.name, "mymod");
+        assert_eq!(info.version, Version::new(1, 2, 3));
+        assert_eq!(info.factorio_version, FactorioVersion::new(1, 1));
+        assert_eq!(
+            info.dependencies,
+            vec![ModDependency::try_from("base >= 1.1.0")?, ModDependency::try_from("? othermod")?]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn info_json_in_directory() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        std::fs::write(
+            dir.path().join("info.json"),
+            r#"{ "name": "mymod", "version": "0.1.0", "title": "My Mod", "author": "someone" }"#,
+        )?;
+
+        let info = read_info_json(dir.path())?;
+
+        assert_eq!(info.name, "mymod");
+        assert_eq!(info.factorio_version, FactorioVersion::new(0, 12));
+        assert_eq!(info.dependencies, vec![ModDependency::unversioned("base".into())]);
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+//! A persistent on-disk cache for mod portal responses.
+
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::Result;
+
+/// Configuration of the on-disk cache of a [`ModPortalClient`].
+///
+/// Responses are stored as raw JSON together with their `ETag` and
+/// `Last-Modified` headers. Entries younger than the TTL are used without
+/// contacting the mod portal, older ones are revalidated with a conditional
+/// request.
+///
+/// [`ModPortalClient`]: crate::ModPortalClient
+#[derive(Clone, Debug)]
+pub struct DiskCache {
+    directory: PathBuf,
+    ttl: Duration,
+    offline: bool,
+}
+
+/// Default time during which cache entries are used without revalidation.
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+impl DiskCache {
+    /// Creates a cache configuration that stores its entries in `directory`.
+    /// The directory is created on demand.
+    pub fn new(directory: impl Into<PathBuf>) -> DiskCache {
+        DiskCache { directory: directory.into(), ttl: DEFAULT_TTL, offline: false }
+    }
+
+    /// Sets the time during which cache entries are used without
+    /// revalidation. Defaults to one hour.
+    pub fn with_ttl(self, ttl: Duration) -> DiskCache {
+        DiskCache { ttl, ..self }
+    }
+
+    /// In offline mode, all requests are answered from the cache, regardless
+    /// of the age of the entries. Requests for which no entry exists fail with
+    /// [`FactorioModApiError::NotCached`].
+    ///
+    /// [`FactorioModApiError::NotCached`]: crate::FactorioModApiError::NotCached
+    pub fn offline(self, offline: bool) -> DiskCache {
+        DiskCache { offline, ..self }
+    }
+
+    /// The directory in which cache entries are stored.
+    pub fn directory(&self) -> &PathBuf {
+        &self.directory
+    }
+
+    pub(crate) fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub(crate) fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Removes all entries from the cache.
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_dir_all(&self.directory) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn entry_path(&self, url: &Url) -> PathBuf {
+        let key = hex::encode(Sha1::digest(url.as_str().as_bytes()));
+        self.directory.join(format!("{key}.json"))
+    }
+
+    /// Loads the cache entry for `url`, if one exists. Unreadable entries are
+    /// treated as missing.
+    pub(crate) fn load(&self, url: &Url) -> Result<Option<CacheEntry>> {
+        let path = self.entry_path(url);
+        let contents = match fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        match serde_json::from_slice::<CacheEntry>(&contents) {
+            Ok(entry) if entry.url == url.as_str() => Ok(Some(entry)),
+            Ok(_) => Ok(None),
+            Err(e) => {
+                warn!("ignoring corrupt cache entry {}: {e}", path.display());
+                Ok(None)
+            }
+        }
+    }
+
+    /// Stores the cache entry for `url`.
+    pub(crate) fn store(&self, url: &Url, entry: &CacheEntry) -> Result<()> {
+        let path = self.entry_path(url);
+        debug!("storing cache entry for {url} in {}", path.display());
+
+        // Write to a temporary file first, so that concurrent stores never
+        // leave a partially written entry behind.
+        fs::create_dir_all(&self.directory)?;
+        let mut temp_file = tempfile::NamedTempFile::new_in(&self.directory)?;
+        temp_file.write_all(&serde_json::to_vec(entry)?)?;
+        temp_file.persist(path).map_err(|e| e.error)?;
+        Ok(())
+    }
+}
+
+/// A raw response stored in the [`DiskCache`].
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct CacheEntry {
+    pub url: String,
+
+    /// Seconds since the UNIX epoch at which the entry was last validated.
+    pub fetched_at: u64,
+
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: String,
+}
+
+impl CacheEntry {
+    pub fn new(
+        url: &Url,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: String,
+    ) -> CacheEntry {
+        CacheEntry { url: url.to_string(), fetched_at: now(), etag, last_modified, body }
+    }
+
+    /// Whether the entry was validated less than `ttl` ago.
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        now().saturating_sub(self.fetched_at) < ttl.as_secs()
+    }
+
+    /// Marks the entry as just validated.
+    pub fn touch(&mut self) {
+        self.fetched_at = now();
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
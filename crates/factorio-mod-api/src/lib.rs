@@ -2,28 +2,58 @@
 //! API](https://wiki.factorio.com/Mod_portal_API).
 
 pub mod api;
+pub mod archive;
+pub mod cache;
+pub mod lockfile;
+pub mod mod_settings;
+pub mod mods_dir;
+mod publish;
+pub mod resolver;
+pub mod retry;
 
 use std::{
-    fs::File,
-    io::Write,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
     path::{Path, PathBuf},
+    time::Duration,
 };
 
-use api::{ApiToken, FullModSpec};
+use api::{
+    ApiToken, FactorioVersion, FullModSpec, ModListQuery, ModListResponse, ModListing, ModRelease,
+    ModSpec, PublishErrorKind,
+};
+use cache::{CacheEntry, DiskCache};
 use elsa::FrozenMap;
-use futures::StreamExt;
-use reqwest::Url;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use reqwest::{
+    header::{CONTENT_RANGE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE},
+    RequestBuilder, Response, StatusCode, Url,
+};
+use retry::RetryPolicy;
 use semver::Version;
+use serde::de::DeserializeOwned;
+use sha1::{Digest, Sha1};
 use thiserror::Error;
-use tracing::info;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::{debug, info, warn};
 
 use crate::api::LoginResponse;
 
 /// A simple caching client for the [Factorio mod portal
 /// API](https://wiki.factorio.com/Mod_portal_API).
+///
+/// Mod specs are always cached in memory. Additionally, a persistent
+/// [`DiskCache`] can be configured with [`ModPortalClient::with_disk_cache`].
+///
+/// Failed requests are retried according to a [`RetryPolicy`], and the number
+/// of concurrent requests is limited to avoid overloading the mod portal.
 pub struct ModPortalClient {
     client: reqwest::Client,
     specs: FrozenMap<String, Box<FullModSpec>>,
+    short_specs: FrozenMap<String, Box<ModSpec>>,
+    disk_cache: Option<DiskCache>,
+    retry_policy: RetryPolicy,
+    request_limiter: Semaphore,
     mod_api_base: Url,
     auth_api_base: Url,
 }
@@ -49,7 +79,45 @@ impl ModPortalClient {
         mod_api_base: Url,
         auth_api_base: Url,
     ) -> Result<ModPortalClient> {
-        Ok(ModPortalClient { client, specs: FrozenMap::new(), mod_api_base, auth_api_base })
+        Ok(ModPortalClient {
+            client,
+            specs: FrozenMap::new(),
+            short_specs: FrozenMap::new(),
+            disk_cache: None,
+            retry_policy: RetryPolicy::default(),
+            request_limiter: Semaphore::new(DEFAULT_MAX_CONCURRENT_REQUESTS),
+            mod_api_base,
+            auth_api_base,
+        })
+    }
+
+    /// Enables a persistent on-disk cache for API responses.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::{cache::DiskCache, ModPortalClient};
+    /// use std::time::Duration;
+    ///
+    /// let cache = DiskCache::new("/tmp/mod-portal").with_ttl(Duration::from_secs(600));
+    /// let client = ModPortalClient::new()?.with_disk_cache(cache);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_disk_cache(self, disk_cache: DiskCache) -> ModPortalClient {
+        ModPortalClient { disk_cache: Some(disk_cache), ..self }
+    }
+
+    /// Sets the policy for retrying failed requests.
+    pub fn with_retry_policy(self, retry_policy: RetryPolicy) -> ModPortalClient {
+        ModPortalClient { retry_policy, ..self }
+    }
+
+    /// Sets the maximum number of requests that are sent concurrently.
+    /// Defaults to 8.
+    pub fn with_max_concurrent_requests(self, max: usize) -> ModPortalClient {
+        ModPortalClient { request_limiter: Semaphore::new(max), ..self }
     }
 
     /// Get the full spec of a Factorio mod. Request results are cached in memory.
@@ -72,15 +140,88 @@ impl ModPortalClient {
             spec
         } else {
             info!("requesting mod spec for '{name}'");
-            let url = self
-                .mod_api_base
-                .join(&format!("api/mods/{name}/full"))
-                .map_err(|_| FactorioModApiError::InvalidModName { name: name.into() })?;
-            let response = self.client.get(url).send().await?.json().await?;
+            let url = self.mod_url(name, "/full")?;
+            let response = self.get_json(url).await?;
             self.specs.insert(name.into(), Box::new(response))
         })
     }
 
+    /// Get the short spec of a Factorio mod. This is considerably smaller than
+    /// the full spec, but the releases it contains don't list their
+    /// dependencies. Request results are cached in memory. If the full spec of
+    /// the mod was already requested, it is used instead of sending another
+    /// request.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::ModPortalClient;
+    ///
+    /// let client = ModPortalClient::new()?;
+    /// let spec = client.get_mod_short_spec("my_mod").await?;
+    /// println!("{}", spec.metadata.title);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_mod_short_spec(&self, name: &str) -> Result<&ModSpec> {
+        Ok(if let Some(spec) = self.specs.get(name) {
+            info!("returning short mod spec for '{name}' from full spec cache");
+            &spec.short_spec
+        } else if let Some(spec) = self.short_specs.get(name) {
+            info!("returning short mod spec for '{name}' from cache");
+            spec
+        } else {
+            info!("requesting short mod spec for '{name}'");
+            let url = self.mod_url(name, "")?;
+            let response = self.get_json(url).await?;
+            self.short_specs.insert(name.into(), Box::new(response))
+        })
+    }
+
+    /// List mods on the mod portal, optionally filtered and sorted according to
+    /// `query`. The returned stream follows the pagination links of the API
+    /// and requests further pages lazily as the stream is consumed. Results
+    /// are not cached.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::{api::ModListQuery, ModPortalClient};
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = ModPortalClient::new()?;
+    /// let query = ModListQuery { version: Some("1.1".parse()?), ..Default::default() };
+    /// let mods: Vec<_> = client.list_mods(&query).try_collect().await?;
+    /// println!("{} mods", mods.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_mods(&self, query: &ModListQuery) -> impl Stream<Item = Result<ModListing>> + '_ {
+        let mut url = self.mod_api_base.join("api/mods").unwrap();
+        let pairs = query.to_query_pairs();
+        if !pairs.is_empty() {
+            url.query_pairs_mut().extend_pairs(pairs);
+        }
+
+        stream::try_unfold(Some(url), move |url| async move {
+            let Some(url) = url else { return Ok::<_, FactorioModApiError>(None) };
+
+            info!("requesting mod list: {url}");
+            let response: ModListResponse = self.get_json(url).await?;
+
+            let next = response
+                .pagination
+                .and_then(|p| p.links.next)
+                .map(|next| self.mod_api_base.join(&next))
+                .transpose()?;
+
+            Ok(Some((stream::iter(response.results.into_iter().map(Ok)), next)))
+        })
+        .try_flatten()
+    }
+
     /// Get a login token needed to invoke authenticated APIs.
     ///
     /// # Example
@@ -103,8 +244,8 @@ impl ModPortalClient {
         let url = self.auth_api_base.join("api-login").unwrap();
         let query = [("api_version", "4"), ("username", user_name), ("password", password)];
 
-        let request = self.client.post(url).query(&query);
-        let response = request.send().await?.json().await?;
+        let (response, _permit) = self.send(self.client.post(url).query(&query)).await?;
+        let response = check_server_status(response)?.json().await?;
 
         match response {
             LoginResponse::Success { token } => Ok(token),
@@ -119,6 +260,9 @@ impl ModPortalClient {
     /// This is an authenticated endpoint that needs a login token to be
     /// obtained with [`ModPortalClient::login`] first.
     ///
+    /// See [`ModPortalClient::download_release`] for how the downloaded file is
+    /// verified.
+    ///
     /// # Example
     /// ```no_run
     /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
@@ -141,30 +285,397 @@ impl ModPortalClient {
     ) -> Result<PathBuf> {
         info!("downloading version {version} of '{mod_name}' mod");
 
-        let releases = &self.get_mod_spec(mod_name).await?.short_spec.releases;
-        let Some(release) = releases.iter().find(|r| r.version == *version) else {
-            return Err(FactorioModApiError::InvalidModVersion { version: version.clone() });
+        let release = self.get_release(mod_name, version).await?;
+        self.download_release(release, api_token, directory).await
+    }
+
+    /// Look up a specific release of a mod, using the (cached) short spec.
+    pub async fn get_release(&self, mod_name: &str, version: &Version) -> Result<&ModRelease> {
+        let releases = &self.get_mod_short_spec(mod_name).await?.releases;
+        releases
+            .iter()
+            .find(|r| r.version == *version)
+            .ok_or_else(|| FactorioModApiError::InvalidModVersion { version: version.clone() })
+    }
+
+    /// Look up the latest release of a mod, using the (cached) short spec. If
+    /// `factorio_version` is given, only releases made for that version of the
+    /// game are considered.
+    pub async fn get_latest_release(
+        &self,
+        mod_name: &str,
+        factorio_version: Option<FactorioVersion>,
+    ) -> Result<&ModRelease> {
+        let releases = &self.get_mod_short_spec(mod_name).await?.releases;
+        releases
+            .iter()
+            .filter(|r| {
+                factorio_version.is_none_or(|v| r.info_json.factorio_version.is_compatible_with(&v))
+            })
+            .max_by_key(|r| &r.version)
+            .ok_or_else(|| FactorioModApiError::NoMatchingRelease {
+                mod_name: mod_name.into(),
+                factorio_version,
+            })
+    }
+
+    /// Download a specific release of a mod from the mod portal.
+    ///
+    /// This is an authenticated endpoint that needs a login token to be
+    /// obtained with [`ModPortalClient::login`] first.
+    ///
+    /// See [`ModPortalClient::download_release_with_progress`] for details.
+    pub async fn download_release(
+        &self,
+        release: &ModRelease,
+        api_token: &ApiToken,
+        directory: &Path,
+    ) -> Result<PathBuf> {
+        self.download_release_with_progress(release, api_token, directory, |_| {}).await
+    }
+
+    /// Download a specific release of a mod from the mod portal, reporting the
+    /// progress to `on_progress` after each received chunk.
+    ///
+    /// The file is first written to a temporary `.part` file next to its final
+    /// location and only renamed once its SHA-1 hash was verified to match
+    /// [`ModRelease::sha1`]. If the file already exists with the correct hash,
+    /// nothing is downloaded. If a `.part` file is left over from an
+    /// interrupted download, the download is resumed with an HTTP range
+    /// request.
+    ///
+    /// This is an authenticated endpoint that needs a login token to be
+    /// obtained with [`ModPortalClient::login`] first.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::ModPortalClient;
+    /// use semver::Version;
+    /// use std::env;
+    ///
+    /// let client = ModPortalClient::new()?;
+    /// let token = client.login("my_user", "my_password").await?;
+    /// let release = client.get_release("my_mod", &Version::parse("1.0.0")?).await?;
+    /// client
+    ///     .download_release_with_progress(release, &token, &env::current_dir()?, |p| {
+    ///         println!("{}/{:?}", p.received, p.total)
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_release_with_progress(
+        &self,
+        release: &ModRelease,
+        api_token: &ApiToken,
+        directory: &Path,
+        on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<PathBuf> {
+        let file = DownloadFile {
+            download_url: &release.download_url,
+            file_name: &release.file_name,
+            sha1: &release.sha1,
         };
+        self.download_file(file, api_token, directory, on_progress).await
+    }
+
+    /// Downloads a file from the mod portal and verifies its SHA-1 hash. See
+    /// [`ModPortalClient::download_release_with_progress`].
+    async fn download_file(
+        &self,
+        file: DownloadFile<'_>,
+        api_token: &ApiToken,
+        directory: &Path,
+        mut on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<PathBuf> {
+        let (url, filepath) = self.download_target(&file, directory)?;
+        if filepath.is_file() && file_sha1(&filepath)? == file.sha1 {
+            info!("{} already exists, skipping download", filepath.display());
+            return Ok(filepath);
+        }
 
-        let url = self
-            .mod_api_base
-            .join(&release.download_url)
-            .expect("the mod api shouldn't return invalid URLs");
         let query = [("username", &api_token.username), ("token", &api_token.token)];
 
-        let response = self.client.get(url).query(&query).send().await?;
+        let part_path = part_path(&filepath);
+        let mut hasher = Sha1::new();
+        let mut received = 0;
+        if part_path.is_file() {
+            received = io::copy(&mut File::open(&part_path)?, &mut hasher)?;
+        }
 
-        let filepath = directory.join(&release.file_name);
-        let mut file = File::create(&filepath)?;
-        let mut stream = response.bytes_stream();
+        let mut request = self.client.get(url.clone()).query(&query);
+        if received > 0 {
+            info!("resuming download of {} at byte {received}", file.file_name);
+            request = request.header(RANGE, format!("bytes={received}-"));
+        }
+        let (mut response, mut permit) = self.send(request).await?;
+
+        // A partial response that doesn't start where the `.part` file ends
+        // would corrupt the download, so it's restarted instead.
+        let resumed = received > 0 && resumes_at(&response, received);
+        if !resumed
+            && matches!(
+                response.status(),
+                StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE
+            )
+        {
+            debug!("can't resume download of {}, restarting", file.file_name);
+            drop(permit);
+            (response, permit) = self.send(self.client.get(url).query(&query)).await?;
+        }
+        let response = check_status(response)?;
+
+        let mut part_file = if resumed {
+            OpenOptions::new().append(true).open(&part_path)?
+        } else {
+            hasher = Sha1::new();
+            received = 0;
+            File::create(&part_path)?
+        };
+
+        let total = response.content_length().map(|length| length + received);
+        on_progress(DownloadProgress { received, total });
 
+        let mut stream = response.bytes_stream();
         while let Some(item) = stream.next().await {
             let chunk = item?;
-            file.write_all(&chunk)?;
+            hasher.update(&chunk);
+            part_file.write_all(&chunk)?;
+            received += chunk.len() as u64;
+            on_progress(DownloadProgress { received, total });
+        }
+        drop(part_file);
+        drop(permit);
+
+        let actual = hex::encode(hasher.finalize());
+        if actual != file.sha1 {
+            fs::remove_file(&part_path)?;
+            return Err(FactorioModApiError::ChecksumMismatch {
+                file: filepath,
+                expected: file.sha1.into(),
+                actual,
+            });
         }
 
+        fs::rename(&part_path, &filepath)?;
         Ok(filepath)
     }
+
+    /// The URL and local path of a download. Both are checked, because the
+    /// release data can come from an untrusted lockfile: the URL must point to
+    /// the mod portal, so that the login token isn't sent anywhere else, and
+    /// the file name must be a plain `.zip` file name inside `directory`.
+    fn download_target(&self, file: &DownloadFile, directory: &Path) -> Result<(Url, PathBuf)> {
+        let invalid = |reason: &str| FactorioModApiError::InvalidDownload {
+            file_name: file.file_name.into(),
+            reason: reason.into(),
+        };
+
+        let url = self
+            .mod_api_base
+            .join(file.download_url)
+            .map_err(|_| invalid("invalid download URL"))?;
+        if url.origin() != self.mod_api_base.origin() {
+            return Err(invalid("the download URL doesn't point to the mod portal"));
+        }
+
+        let is_plain_name = !file.file_name.contains(['/', '\\', ':'])
+            && file.file_name.strip_suffix(".zip").is_some_and(|stem| !stem.is_empty());
+        if !is_plain_name {
+            return Err(invalid("the file name isn't a plain .zip file name"));
+        }
+
+        Ok((url, directory.join(file.file_name)))
+    }
+
+    /// Sends a GET request and parses the JSON response, going through the
+    /// disk cache if one is configured.
+    async fn get_json<T: DeserializeOwned>(&self, url: Url) -> Result<T> {
+        let Some(cache) = &self.disk_cache else {
+            let (response, _permit) = self.send(self.client.get(url)).await?;
+            return Ok(check_status(response)?.json().await?);
+        };
+
+        let entry = cache.load(&url)?;
+        match &entry {
+            Some(entry) if cache.is_offline() || entry.is_fresh(cache.ttl()) => {
+                debug!("returning {url} from disk cache");
+                return Ok(serde_json::from_str(&entry.body)?);
+            }
+            None if cache.is_offline() => {
+                return Err(FactorioModApiError::NotCached { url: url.into() })
+            }
+            _ => {}
+        }
+
+        let mut request = self.client.get(url.clone());
+        if let Some(entry) = &entry {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let (response, _permit) = self.send(request).await?;
+
+        if let (StatusCode::NOT_MODIFIED, Some(mut entry)) = (response.status(), entry) {
+            debug!("disk cache entry for {url} is still valid");
+            entry.touch();
+            cache.store(&url, &entry)?;
+            return Ok(serde_json::from_str(&entry.body)?);
+        }
+
+        let response = check_status(response)?;
+        let header =
+            |name| response.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from);
+        let (etag, last_modified) = (header(ETAG), header(LAST_MODIFIED));
+        let body = response.text().await?;
+        let parsed = serde_json::from_str(&body)?;
+
+        cache.store(&url, &CacheEntry::new(&url, etag, last_modified, body))?;
+
+        Ok(parsed)
+    }
+
+    /// Sends a request, retrying it according to the retry policy. Responses
+    /// with an error status are returned as they are once all retries are
+    /// exhausted; use [`check_status`] to turn them into errors.
+    ///
+    /// The response comes with a permit of the request limiter, which needs
+    /// to be held until the response body was read.
+    async fn send(&self, mut request: RequestBuilder) -> Result<(Response, SemaphorePermit<'_>)> {
+        let mut attempt = 0;
+        loop {
+            // Requests with streaming bodies can't be cloned and aren't retried.
+            let next =
+                if attempt < self.retry_policy.max_retries { request.try_clone() } else { None };
+
+            let permit = self.request_limiter.acquire().await.expect("semaphore is never closed");
+            let result = request.send().await;
+
+            let Some(next) = next else { return Ok((result?, permit)) };
+            let delay = match &result {
+                Ok(response) if retry::is_retryable(response.status()) => {
+                    warn!("request to {} failed with {}", response.url().path(), response.status());
+                    match retry::retry_after(response) {
+                        // Give up instead of waiting for hours.
+                        Some(delay) if delay > self.retry_policy.max_backoff => {
+                            warn!("server asks to retry in {delay:?}, giving up");
+                            return Ok((result?, permit));
+                        }
+                        Some(delay) => delay,
+                        None => self.retry_policy.backoff(attempt),
+                    }
+                }
+                Err(e) if retry::is_retryable_error(e) => {
+                    warn!("request failed: {e}");
+                    self.retry_policy.backoff(attempt)
+                }
+                _ => return Ok((result?, permit)),
+            };
+            drop(permit);
+
+            info!("retrying in {delay:?}");
+            tokio::time::sleep(delay).await;
+            request = next;
+            attempt += 1;
+        }
+    }
+
+    /// Builds the URL of a per-mod endpoint, e.g. `api/mods/{name}/full`.
+    fn mod_url(&self, name: &str, suffix: &str) -> Result<Url> {
+        self.mod_api_base
+            .join(&format!("api/mods/{name}{suffix}"))
+            .map_err(|_| FactorioModApiError::InvalidModName { name: name.into() })
+    }
+}
+
+/// Default limit for the number of concurrent requests.
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Turns responses with an error status into the matching
+/// [`FactorioModApiError`].
+fn check_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    // Don't leak login tokens into error messages.
+    let mut url = response.url().clone();
+    url.set_query(None);
+    let url = url.to_string();
+
+    Err(match status {
+        StatusCode::NOT_FOUND => FactorioModApiError::NotFound { url },
+        StatusCode::TOO_MANY_REQUESTS => {
+            FactorioModApiError::RateLimited { retry_after: retry::retry_after(&response) }
+        }
+        status if status.is_server_error() => {
+            FactorioModApiError::ServerError { status: status.as_u16(), url }
+        }
+        status => FactorioModApiError::HttpError { status: status.as_u16(), url },
+    })
+}
+
+/// Like [`check_status`], but lets client errors pass. This is used for
+/// endpoints that describe errors in the response body.
+fn check_server_status(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS {
+        Ok(response)
+    } else {
+        check_status(response)
+    }
+}
+
+/// The parts of a release that are needed to download it.
+struct DownloadFile<'a> {
+    download_url: &'a str,
+    file_name: &'a str,
+    sha1: &'a str,
+}
+
+/// Progress of a download, as reported by
+/// [`ModPortalClient::download_release_with_progress`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DownloadProgress {
+    /// Number of bytes received so far, including those of a resumed partial
+    /// download.
+    pub received: u64,
+
+    /// Total size of the file, if known from the `Content-Length` header.
+    pub total: Option<u64>,
+}
+
+/// Path of the temporary file that a download to `path` is written to.
+fn part_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_owned();
+    file_name.push(".part");
+    path.with_file_name(file_name)
+}
+
+/// Computes the hex encoded SHA-1 hash of a file's contents.
+/// Whether `response` is a `206 Partial Content` response whose
+/// `Content-Range` header, e.g. `bytes 6-14/15`, starts at `offset`.
+fn resumes_at(response: &Response, offset: u64) -> bool {
+    let start = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("bytes "))
+        .and_then(|range| range.split_once('-'))
+        .and_then(|(start, _)| start.trim().parse::<u64>().ok());
+    response.status() == StatusCode::PARTIAL_CONTENT && start == Some(offset)
+}
+
+fn file_sha1(path: &Path) -> Result<String> {
+    let mut hasher = Sha1::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
 }
 
 /// Main result type used throughout factorio-mod-api
@@ -185,10 +696,43 @@ pub enum FactorioModApiError {
     #[error("Invalid mod version: '{version}'")]
     InvalidModVersion { version: Version },
 
+    /// Error that is raised if a mod has no release for the requested version
+    /// of Factorio.
+    #[error("no release of '{mod_name}' found{}", factorio_version.map(|v| format!(" for Factorio {v}")).unwrap_or_default())]
+    NoMatchingRelease { mod_name: String, factorio_version: Option<FactorioVersion> },
+
+    /// Error that is raised if a Factorio version isn't of the form
+    /// `major.minor`.
+    #[error("Invalid Factorio version: '{version}'")]
+    InvalidFactorioVersion { version: String },
+
     /// Error that is raised if a request to the mod portal failed.
     #[error("Error while talking to the API Server")]
     RequestError(#[from] reqwest::Error),
 
+    /// Error that is raised if the mod portal doesn't know the requested
+    /// resource, e.g. a mod that doesn't exist.
+    #[error("'{url}' not found on the mod portal")]
+    NotFound { url: String },
+
+    /// Error that is raised if the mod portal still rejects requests because
+    /// of rate limiting after all retries are exhausted.
+    #[error("rate limited by the mod portal")]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// Error that is raised if the mod portal still responds with a server
+    /// error after all retries are exhausted.
+    #[error("mod portal server error {status} for '{url}'")]
+    ServerError { status: u16, url: String },
+
+    /// Error that is raised for any other unsuccessful HTTP status.
+    #[error("unexpected HTTP status {status} for '{url}'")]
+    HttpError { status: u16, url: String },
+
+    /// Error that is raised if the mod portal returns an invalid URL.
+    #[error("invalid URL")]
+    UrlError(#[from] url::ParseError),
+
     /// Error that is raised if parsing of a SemVer version number failed.
     #[error("Error while parsing a version number")]
     VersionError(#[from] semver::Error),
@@ -197,30 +741,74 @@ pub enum FactorioModApiError {
     #[error("failed to parse JSON")]
     JsonParsingError(#[from] serde_json::Error),
 
+    /// Error that is raised if a downloaded file doesn't match the checksum
+    /// published by the mod portal.
+    #[error("checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch { file: PathBuf, expected: String, actual: String },
+
+    /// Error that is raised if a release can't be downloaded safely, because
+    /// its download URL doesn't point to the mod portal or its file name isn't
+    /// a plain file name.
+    #[error("refusing to download '{file_name}': {reason}")]
+    InvalidDownload { file_name: String, reason: String },
+
+    /// Error that is raised in offline mode if a response isn't available in
+    /// the disk cache.
+    #[error("'{url}' is not available in the offline cache")]
+    NotCached { url: String },
+
     #[error("failed to log in: {error}, {message}")]
     LoginError { error: String, message: String },
 
+    /// Error that is raised if the mod portal rejects a publishing request.
+    #[error("mod portal rejected the request: {kind}, {}", message.as_deref().unwrap_or_default())]
+    PublishError { kind: PublishErrorKind, message: Option<String> },
+
+    /// Error that is raised if no set of mod versions satisfies all
+    /// dependencies.
+    #[error("{0}")]
+    ResolutionConflict(#[from] resolver::Conflict),
+
+    /// Error that is raised if a file isn't a valid mod archive.
+    #[error("{path} is not a valid mod archive: {reason}")]
+    InvalidModArchive { path: PathBuf, reason: String },
+
+    /// Error that is raised if a `mod-settings.dat` file can't be parsed.
+    #[error("invalid mod settings: {reason}")]
+    InvalidModSettings { reason: String },
+
+    /// Error that is raised if a mod archive couldn't be read.
+    #[error("Error while reading a zip archive")]
+    ZipError(#[from] zip::result::ZipError),
+
     #[error("Error while doing an IO operation")]
     IOError(#[from] std::io::Error),
 }
 
 #[cfg(test)]
-mod tests {
-    use std::error::Error;
+pub(crate) mod tests {
+    use std::{error::Error, fs, time::Duration};
 
     use chrono::{DateTime, Utc};
+    use futures::TryStreamExt;
     use httpmock::prelude::*;
     use ordered_float::NotNan;
     use pretty_assertions::assert_eq;
     use reqwest::Url;
     use semver::Version;
+    use sha1::{Digest, Sha1};
 
     use crate::{
-        api::{ModManifest, ModMetadata, ModRelease, ModSpec, ModTag},
-        ModPortalClient,
+        api::{
+            ApiToken, FactorioVersion, ModListQuery, ModManifest, ModMetadata, ModRelease, ModSpec,
+            ModTag, PageSize,
+        },
+        cache::DiskCache,
+        retry::RetryPolicy,
+        DownloadProgress, FactorioModApiError, ModPortalClient,
     };
 
-    fn setup() -> Result<(MockServer, ModPortalClient), Box<dyn Error>> {
+    pub fn setup() -> Result<(MockServer, ModPortalClient), Box<dyn Error>> {
         let server = MockServer::start();
         let client = ModPortalClient::with_base_urls(
             reqwest::Client::default(),
@@ -231,7 +819,19 @@ mod tests {
         Ok((server, client))
     }
 
-    fn mock_full(server: &MockServer) -> httpmock::Mock {
+    fn cached_client(
+        server: &MockServer,
+        cache: DiskCache,
+    ) -> Result<ModPortalClient, Box<dyn Error>> {
+        Ok(ModPortalClient::with_base_urls(
+            reqwest::Client::default(),
+            Url::parse(&server.base_url())?,
+            Url::parse(&server.base_url())?,
+        )?
+        .with_disk_cache(cache))
+    }
+
+    fn mock_full(server: &MockServer) -> httpmock::Mock<'_> {
         server.mock(|when, then| {
             when.method(GET).path("/api/mods/mymod/full");
             then.status(200)
@@ -263,7 +863,7 @@ mod tests {
                         download_url: "/download/mymod/bde93f095d1b53ed019fca5e".into(),
                         file_name: "mymod_0.0.1.zip".into(),
                         info_json: ModManifest {
-                            factorio_version: "0.14".into(),
+                            factorio_version: FactorioVersion::new(0, 14),
                             dependencies: Some(vec!["base >= 0.13.0".try_into()?]),
                         },
                         released_at: DateTime::from_naive_utc_and_offset(
@@ -278,7 +878,7 @@ mod tests {
                         download_url: "/download/mymod/7303634ba9642c5a321a757e".into(),
                         file_name: "mymod_0.0.2.zip".into(),
                         info_json: ModManifest {
-                            factorio_version: "0.14".into(),
+                            factorio_version: FactorioVersion::new(0, 14),
                             dependencies: Some(vec!["base >= 0.13.0".try_into()?]),
                         },
                         released_at: DateTime::from_naive_utc_and_offset(
@@ -316,4 +916,511 @@ mod tests {
         mock.assert_hits(1);
         Ok(())
     }
+
+    /// Mocks the short spec of a mod with a single release whose published
+    /// checksum is `sha1`, and the download of that release returning
+    /// `content`.
+    fn mock_download<'a>(
+        server: &'a MockServer,
+        content: &'static [u8],
+        sha1: &str,
+    ) -> (httpmock::Mock<'a>, httpmock::Mock<'a>) {
+        let spec = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod");
+            then.status(200).json_body(serde_json::json!({
+                "name": "mymod",
+                "owner": "someone",
+                "summary": "SUMMARY",
+                "title": "TITLE",
+                "downloads_count": 42,
+                "score": 1.0,
+                "releases": [{
+                    "download_url": "/download/mymod/abc",
+                    "file_name": "mymod_1.0.0.zip",
+                    "info_json": { "factorio_version": "1.1" },
+                    "released_at": "2022-06-14T11:45:45.165000Z",
+                    "version": "1.0.0",
+                    "sha1": sha1
+                }]
+            }));
+        });
+        let download = server.mock(|when, then| {
+            when.method(GET)
+                .path("/download/mymod/abc")
+                .query_param("username", "user")
+                .query_param("token", "token");
+            then.status(200).body(content);
+        });
+        (spec, download)
+    }
+
+    pub fn token() -> ApiToken {
+        ApiToken { token: "token".into(), username: "user".into() }
+    }
+
+    #[tokio::test]
+    async fn download_verifies_checksum() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let content = b"PK zip contents";
+        let (_, download) = mock_download(&server, content, &hex::encode(Sha1::digest(content)));
+        let dir = tempfile::tempdir()?;
+
+        let path =
+            client.download_mod("mymod", &Version::parse("1.0.0")?, &token(), dir.path()).await?;
+
+        assert_eq!(path, dir.path().join("mymod_1.0.0.zip"));
+        assert_eq!(fs::read(&path)?, content);
+        assert!(!dir.path().join("mymod_1.0.0.zip.part").exists());
+
+        client.download_mod("mymod", &Version::parse("1.0.0")?, &token(), dir.path()).await?;
+        download.assert_hits(1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_checksum_mismatch() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let (_, download) = mock_download(&server, b"truncated", "0000");
+        let dir = tempfile::tempdir()?;
+
+        let result =
+            client.download_mod("mymod", &Version::parse("1.0.0")?, &token(), dir.path()).await;
+
+        assert!(matches!(result, Err(FactorioModApiError::ChecksumMismatch { .. })));
+        assert_eq!(fs::read_dir(dir.path())?.count(), 0);
+        download.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_reports_progress() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let content = b"PK zip contents";
+        mock_download(&server, content, &hex::encode(Sha1::digest(content)));
+        let dir = tempfile::tempdir()?;
+
+        let release = client.get_release("mymod", &Version::parse("1.0.0")?).await?;
+        let mut progress = Vec::new();
+        client
+            .download_release_with_progress(release, &token(), dir.path(), |p| progress.push(p))
+            .await?;
+
+        let total = Some(content.len() as u64);
+        assert_eq!(progress.first(), Some(&DownloadProgress { received: 0, total }));
+        assert_eq!(
+            progress.last(),
+            Some(&DownloadProgress { received: content.len() as u64, total })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_resumes_part_file() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let content = b"PK zip contents";
+        let ranged_download = server.mock(|when, then| {
+            when.method(GET).path("/download/mymod/abc").header("range", "bytes=6-");
+            then.status(206).header("content-range", "bytes 6-14/15").body(&content[6..]);
+        });
+        let (_, full_download) =
+            mock_download(&server, content, &hex::encode(Sha1::digest(content)));
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("mymod_1.0.0.zip.part"), &content[..6])?;
+
+        let path =
+            client.download_mod("mymod", &Version::parse("1.0.0")?, &token(), dir.path()).await?;
+
+        assert_eq!(fs::read(path)?, content);
+        ranged_download.assert();
+        full_download.assert_hits(0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_restarts_on_wrong_range() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let content = b"PK zip contents";
+        let ranged_download = server.mock(|when, then| {
+            when.method(GET).path("/download/mymod/abc").header("range", "bytes=6-");
+            then.status(206).header("content-range", "bytes 0-14/15").body(content);
+        });
+        let (_, full_download) =
+            mock_download(&server, content, &hex::encode(Sha1::digest(content)));
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("mymod_1.0.0.zip.part"), &content[..6])?;
+
+        let path =
+            client.download_mod("mymod", &Version::parse("1.0.0")?, &token(), dir.path()).await?;
+
+        assert_eq!(fs::read(path)?, content);
+        ranged_download.assert();
+        full_download.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn short_request() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(include_bytes!("tests/short.json"));
+        });
+
+        let spec = client.get_mod_short_spec("mymod").await?;
+        assert_eq!(spec.metadata.name, "mymod");
+        assert_eq!(spec.releases.len(), 2);
+        assert_eq!(spec.releases[0].info_json.dependencies, None);
+
+        client.get_mod_short_spec("mymod").await?;
+
+        mock.assert_hits(1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn latest_release() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(include_bytes!("tests/short.json"));
+        });
+
+        let release = client.get_latest_release("mymod", None).await?;
+        assert_eq!(release.version, Version::new(0, 0, 2));
+
+        let release = client.get_latest_release("mymod", Some(FactorioVersion::new(0, 14))).await?;
+        assert_eq!(release.version, Version::new(0, 0, 2));
+
+        let result = client.get_latest_release("mymod", Some(FactorioVersion::new(1, 1))).await;
+        assert_eq!(result.unwrap_err().to_string(), "no release of 'mymod' found for Factorio 1.1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn short_request_uses_full_spec() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let full = mock_full(&server);
+        let short = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod");
+            then.status(500);
+        });
+
+        let full_spec = client.get_mod_spec("mymod").await?;
+        let short_spec = client.get_mod_short_spec("mymod").await?;
+        assert_eq!(&full_spec.short_spec, short_spec);
+
+        full.assert_hits(1);
+        short.assert_hits(0);
+        Ok(())
+    }
+
+    fn fast_retries() -> RetryPolicy {
+        RetryPolicy { max_retries: 2, initial_backoff: Duration::ZERO, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn unknown_mod() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/nomod/full");
+            then.status(404).json_body(serde_json::json!({ "message": "Mod not found" }));
+        });
+
+        let result = client.get_mod_spec("nomod").await;
+
+        assert!(matches!(result, Err(FactorioModApiError::NotFound { .. })));
+        mock.assert_hits(1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn server_error_is_retried() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let client = client.with_retry_policy(fast_retries());
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod/full");
+            then.status(503);
+        });
+
+        let result = client.get_mod_spec("mymod").await;
+
+        assert!(matches!(result, Err(FactorioModApiError::ServerError { status: 503, .. })));
+        mock.assert_hits(3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rate_limit_honors_retry_after() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let client = client.with_retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_secs(3600),
+            ..fast_retries()
+        });
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod/full");
+            then.status(429).header("retry-after", "0");
+        });
+
+        let result = client.get_mod_spec("mymod").await;
+
+        assert!(matches!(
+            result,
+            Err(FactorioModApiError::RateLimited { retry_after: Some(Duration::ZERO) })
+        ));
+        mock.assert_hits(3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rate_limit_gives_up_on_long_retry_after() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let client = client.with_retry_policy(fast_retries());
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod/full");
+            then.status(429).header("retry-after", "86400");
+        });
+
+        let result =
+            tokio::time::timeout(Duration::from_secs(10), client.get_mod_spec("mymod")).await?;
+
+        assert!(matches!(
+            result,
+            Err(FactorioModApiError::RateLimited { retry_after: Some(d) })
+                if d == Duration::from_secs(86400)
+        ));
+        mock.assert_hits(1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn limiter_covers_response_body() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let client = client.with_max_concurrent_requests(1);
+        server.mock(|when, then| {
+            when.method(GET).path("/file");
+            then.status(200).body("contents");
+        });
+        let request = || client.client.get(server.url("/file"));
+
+        let (response, permit) = client.send(request()).await?;
+        let blocked = tokio::time::timeout(Duration::from_millis(200), client.send(request()));
+        assert!(blocked.await.is_err(), "second request was sent while the body was unread");
+
+        assert_eq!(response.text().await?, "contents");
+        drop(permit);
+        let (response, _permit) = client.send(request()).await?;
+        assert_eq!(response.text().await?, "contents");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let client = client.with_retry_policy(fast_retries());
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod/full");
+            then.status(403);
+        });
+
+        let result = client.get_mod_spec("mymod").await;
+
+        assert!(matches!(result, Err(FactorioModApiError::HttpError { status: 403, .. })));
+        mock.assert_hits(1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_follows_pagination() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+
+        let listing = |name: &str| {
+            serde_json::json!({
+                "name": name,
+                "owner": "someone",
+                "summary": "SUMMARY",
+                "title": "TITLE",
+                "category": "general",
+                "downloads_count": 42,
+                "latest_release": {
+                    "download_url": format!("/download/{name}/abc"),
+                    "file_name": format!("{name}_1.0.0.zip"),
+                    "info_json": { "factorio_version": "1.1" },
+                    "released_at": "2022-06-14T11:45:45.165000Z",
+                    "version": "1.0.0",
+                    "sha1": "65b0435dbd4fb0ab0ceea61549641bf6f7dce9d2"
+                }
+            })
+        };
+
+        let page1 = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/mods")
+                .query_param("page", "1")
+                .query_param("page_size", "1")
+                .query_param("version", "1.1");
+            then.status(200).json_body(serde_json::json!({
+                "pagination": {
+                    "count": 2,
+                    "links": {
+                        "first": null,
+                        "prev": null,
+                        "next": server.url("/api/mods?page=2&page_size=1&version=1.1"),
+                        "last": server.url("/api/mods?page=2&page_size=1&version=1.1"),
+                    },
+                    "page": 1,
+                    "page_count": 2,
+                    "page_size": 1
+                },
+                "results": [listing("mod-a")]
+            }));
+        });
+        let page2 = server.mock(|when, then| {
+            when.method(GET).path("/api/mods").query_param("page", "2");
+            then.status(200).json_body(serde_json::json!({
+                "pagination": {
+                    "count": 2,
+                    "links": {
+                        "first": server.url("/api/mods?page_size=1&version=1.1"),
+                        "prev": server.url("/api/mods?page_size=1&version=1.1"),
+                        "next": null,
+                        "last": null,
+                    },
+                    "page": 2,
+                    "page_count": 2,
+                    "page_size": 1
+                },
+                "results": [listing("mod-b")]
+            }));
+        });
+
+        let query = ModListQuery {
+            page: Some(1),
+            page_size: Some(PageSize::Count(1)),
+            version: Some(FactorioVersion::new(1, 1)),
+            ..Default::default()
+        };
+        let mods: Vec<_> = client.list_mods(&query).try_collect().await?;
+
+        assert_eq!(
+            mods.iter().map(|m| m.metadata.name.as_str()).collect::<Vec<_>>(),
+            ["mod-a", "mod-b"]
+        );
+        assert_eq!(mods[1].latest_release.as_ref().unwrap().version, Version::parse("1.0.0")?);
+
+        page1.assert();
+        page2.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_rejects_invalid_next_link() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        server.mock(|when, then| {
+            when.method(GET).path("/api/mods");
+            then.status(200).json_body(serde_json::json!({
+                "pagination": {
+                    "count": 2,
+                    "links": { "first": null, "prev": null, "next": "https://[oops/", "last": null },
+                    "page": 1,
+                    "page_count": 2,
+                    "page_size": 1
+                },
+                "results": []
+            }));
+        });
+
+        let result: crate::Result<Vec<_>> =
+            client.list_mods(&ModListQuery::default()).try_collect().await;
+
+        assert!(matches!(result, Err(FactorioModApiError::UrlError(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_with_namelist() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods").query_param("namelist", "mod-a,mod-b");
+            then.status(200).json_body(serde_json::json!({ "pagination": null, "results": [] }));
+        });
+
+        let query =
+            ModListQuery { namelist: vec!["mod-a".into(), "mod-b".into()], ..Default::default() };
+        let mods: Vec<_> = client.list_mods(&query).try_collect().await?;
+
+        assert!(mods.is_empty());
+        mock.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disk_cache_survives_client() -> Result<(), Box<dyn Error>> {
+        let server = MockServer::start();
+        let mock = mock_full(&server);
+        let dir = tempfile::tempdir()?;
+
+        cached_client(&server, DiskCache::new(dir.path()))?.get_mod_spec("mymod").await?;
+        let client = cached_client(&server, DiskCache::new(dir.path()))?;
+        let spec = client.get_mod_spec("mymod").await?;
+        assert_eq!(spec.short_spec.metadata.name, "mymod");
+
+        mock.assert_hits(1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disk_cache_revalidates() -> Result<(), Box<dyn Error>> {
+        let server = MockServer::start();
+        let dir = tempfile::tempdir()?;
+        let cache = DiskCache::new(dir.path()).with_ttl(Duration::ZERO);
+
+        let mut initial = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod/full");
+            then.status(200)
+                .header("etag", "\"v1\"")
+                .header("last-modified", "Sat, 24 Sep 2022 08:53:02 GMT")
+                .body(include_bytes!("tests/full.json"));
+        });
+        cached_client(&server, cache.clone())?.get_mod_spec("mymod").await?;
+        initial.assert();
+        initial.delete();
+
+        let revalidation = server.mock(|when, then| {
+            when.method(GET)
+                .path("/api/mods/mymod/full")
+                .header("if-none-match", "\"v1\"")
+                .header("if-modified-since", "Sat, 24 Sep 2022 08:53:02 GMT");
+            then.status(304);
+        });
+        let client = cached_client(&server, cache)?;
+        let spec = client.get_mod_spec("mymod").await?;
+        assert_eq!(spec.short_spec.metadata.name, "mymod");
+
+        revalidation.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disk_cache_offline() -> Result<(), Box<dyn Error>> {
+        let server = MockServer::start();
+        let mock = mock_full(&server);
+        let dir = tempfile::tempdir()?;
+        let cache = DiskCache::new(dir.path()).with_ttl(Duration::ZERO);
+
+        cached_client(&server, cache.clone())?.get_mod_spec("mymod").await?;
+
+        let offline = cached_client(&server, cache.offline(true))?;
+        offline.get_mod_spec("mymod").await?;
+        assert!(matches!(
+            offline.get_mod_spec("othermod").await,
+            Err(FactorioModApiError::NotCached { .. })
+        ));
+
+        mock.assert_hits(1);
+        Ok(())
+    }
 }
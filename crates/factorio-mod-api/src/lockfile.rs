@@ -0,0 +1,214 @@
+//! Lockfiles that pin a resolved set of mods to exact releases.
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::ApiToken, resolver::Resolution, DownloadFile, DownloadProgress, ModPortalClient, Result,
+};
+
+/// A set of mod releases, stored as JSON. It records everything needed to
+/// download exactly these releases again and to verify their contents.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Lockfile {
+    /// The locked mods, sorted by name.
+    pub mods: Vec<LockedMod>,
+}
+
+/// A single mod release in a [`Lockfile`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LockedMod {
+    pub name: String,
+    pub version: Version,
+    pub file_name: String,
+
+    /// The SHA-1 hash of the release file.
+    pub sha1: String,
+
+    /// Path to download the release, relative to the mod portal.
+    pub download_url: String,
+}
+
+impl From<&Resolution> for Lockfile {
+    fn from(resolution: &Resolution) -> Self {
+        Lockfile {
+            mods: resolution
+                .releases
+                .iter()
+                .map(|(name, release)| LockedMod {
+                    name: name.clone(),
+                    version: release.version.clone(),
+                    file_name: release.file_name.clone(),
+                    sha1: release.sha1.clone(),
+                    download_url: release.download_url.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Lockfile {
+    /// Reads a lockfile.
+    pub fn read(path: &Path) -> Result<Lockfile> {
+        Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+    }
+
+    /// Writes the lockfile to `path`, replacing an existing file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut contents = serde_json::to_string_pretty(self)?;
+        contents.push('\n');
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+impl ModPortalClient {
+    /// Downloads a release recorded in a lockfile, reporting the progress to
+    /// `on_progress`. The file is verified against the locked SHA-1 hash.
+    ///
+    /// See [`ModPortalClient::download_release_with_progress`] for details.
+    pub async fn download_locked(
+        &self,
+        locked: &LockedMod,
+        api_token: &ApiToken,
+        directory: &Path,
+        on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<PathBuf> {
+        let file = DownloadFile {
+            download_url: &locked.download_url,
+            file_name: &locked.file_name,
+            sha1: &locked.sha1,
+        };
+        self.download_file(file, api_token, directory, on_progress).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, error::Error, fs};
+
+    use httpmock::prelude::*;
+    use semver::Version;
+    use sha1::{Digest, Sha1};
+
+    use super::{LockedMod, Lockfile};
+    use crate::{
+        api::FullModSpec,
+        resolver::Resolution,
+        tests::{setup, token},
+        FactorioModApiError,
+    };
+
+    fn locked(sha1: &str) -> LockedMod {
+        LockedMod {
+            name: "mymod".into(),
+            version: Version::new(1, 0, 0),
+            file_name: "mymod_1.0.0.zip".into(),
+            sha1: sha1.into(),
+            download_url: "/download/mymod/abc".into(),
+        }
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn Error>> {
+        let spec: FullModSpec = serde_json::from_slice(include_bytes!("tests/full.json"))?;
+        let release = spec.short_spec.releases[0].clone();
+        let resolution = Resolution {
+            requested: vec!["mymod".try_into()?],
+            releases: BTreeMap::from([("mymod".into(), release)]),
+            ..Default::default()
+        };
+
+        let lockfile = Lockfile::from(&resolution);
+        assert_eq!(
+            lockfile.mods,
+            [LockedMod {
+                name: "mymod".into(),
+                version: Version::new(0, 0, 1),
+                file_name: "mymod_0.0.1.zip".into(),
+                sha1: "65b0435dbd4fb0ab0ceea61549641bf6f7dce9d2".into(),
+                download_url: "/download/mymod/bde93f095d1b53ed019fca5e".into(),
+            }]
+        );
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("mods.lock");
+        lockfile.write(&path)?;
+        assert_eq!(Lockfile::read(&path)?, lockfile);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_verifies_locked_hash() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let content = b"PK zip contents";
+        let download = server.mock(|when, then| {
+            when.method(GET).path("/download/mymod/abc");
+            then.status(200).body(content);
+        });
+        let dir = tempfile::tempdir()?;
+
+        let path = client
+            .download_locked(
+                &locked(&hex::encode(Sha1::digest(content))),
+                &token(),
+                dir.path(),
+                |_| {},
+            )
+            .await?;
+        assert_eq!(fs::read(path)?, content);
+
+        fs::remove_file(dir.path().join("mymod_1.0.0.zip"))?;
+        let result = client.download_locked(&locked("0000"), &token(), dir.path(), |_| {}).await;
+        assert!(matches!(result, Err(FactorioModApiError::ChecksumMismatch { .. })));
+        download.assert_hits(2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_rejects_other_hosts() -> Result<(), Box<dyn Error>> {
+        let (_server, client) = setup()?;
+        let other = MockServer::start();
+        let download = other.mock(|when, then| {
+            when.method(GET).path("/x.zip");
+            then.status(200).body("evil");
+        });
+        let dir = tempfile::tempdir()?;
+
+        let locked = LockedMod { download_url: other.url("/x.zip"), ..locked("0000") };
+        let result = client.download_locked(&locked, &token(), dir.path(), |_| {}).await;
+        assert!(matches!(result, Err(FactorioModApiError::InvalidDownload { .. })));
+        download.assert_hits(0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_rejects_paths() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let download = server.mock(|when, then| {
+            when.method(GET).path("/download/mymod/abc");
+            then.status(200).body("evil");
+        });
+        let dir = tempfile::tempdir()?;
+        let mods_dir = dir.path().join("mods");
+        fs::create_dir(&mods_dir)?;
+
+        for file_name in ["../x.zip", "/tmp/x.zip", "mymod.txt", ".zip"] {
+            let locked = LockedMod { file_name: file_name.into(), ..locked("0000") };
+            let result = client.download_locked(&locked, &token(), &mods_dir, |_| {}).await;
+            assert!(
+                matches!(result, Err(FactorioModApiError::InvalidDownload { .. })),
+                "{file_name}: {result:?}"
+            );
+        }
+        download.assert_hits(0);
+        assert!(!dir.path().join("x.zip").exists());
+        Ok(())
+    }
+}
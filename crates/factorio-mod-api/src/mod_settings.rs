@@ -0,0 +1,443 @@
+//! Reading and writing `mod-settings.dat`, the file in which Factorio stores
+//! the values of mod settings.
+//!
+//! The file uses Factorio's binary [property tree] format:
+//! <https://wiki.factorio.com/Mod_settings_file_format>
+//!
+//! [property tree]: https://wiki.factorio.com/Property_tree
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FactorioModApiError, Result};
+
+/// Name of the file that stores mod settings, in the `mods` directory.
+pub const MOD_SETTINGS: &str = "mod-settings.dat";
+
+/// A node of a property tree.
+///
+/// In JSON, nodes are represented by the corresponding JSON values. Integers
+/// are read as [`PropertyTree::SignedInteger`] (or
+/// [`PropertyTree::UnsignedInteger`] if they are too large), numbers with a
+/// fractional part as [`PropertyTree::Number`].
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum PropertyTree {
+    None,
+    Bool(bool),
+    SignedInteger(i64),
+    UnsignedInteger(u64),
+    Number(f64),
+    String(String),
+    List(Vec<PropertyTree>),
+    Dictionary(BTreeMap<String, PropertyTree>),
+}
+
+/// The contents of `mod-settings.dat`: the values of all mod settings, by
+/// setting type and name.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ModSettings {
+    /// The version of Factorio that wrote the file: main, major, minor and
+    /// developer version.
+    pub version: [u16; 4],
+
+    #[serde(default)]
+    pub startup: BTreeMap<String, PropertyTree>,
+
+    #[serde(default)]
+    pub runtime_global: BTreeMap<String, PropertyTree>,
+
+    #[serde(default)]
+    pub runtime_per_user: BTreeMap<String, PropertyTree>,
+}
+
+const STARTUP: &str = "startup";
+const RUNTIME_GLOBAL: &str = "runtime-global";
+const RUNTIME_PER_USER: &str = "runtime-per-user";
+
+impl ModSettings {
+    /// Reads a `mod-settings.dat` file.
+    pub fn read(path: &Path) -> Result<ModSettings> {
+        ModSettings::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the settings to `path`, replacing an existing file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.to_writer(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads mod settings in the format written by Factorio 0.17 or later.
+    pub fn from_reader(mut reader: impl Read) -> Result<ModSettings> {
+        let mut version = [0; 4];
+        for part in &mut version {
+            *part = u16::from_le_bytes(read_bytes(&mut reader)?);
+        }
+        // Unused flag, added in Factorio 0.17.
+        read_u8(&mut reader)?;
+
+        let PropertyTree::Dictionary(mut root) = PropertyTree::read(&mut reader)? else {
+            return Err(invalid("the root node is not a dictionary"));
+        };
+
+        Ok(ModSettings {
+            version,
+            startup: read_section(&mut root, STARTUP)?,
+            runtime_global: read_section(&mut root, RUNTIME_GLOBAL)?,
+            runtime_per_user: read_section(&mut root, RUNTIME_PER_USER)?,
+        })
+    }
+
+    /// Writes the settings in Factorio's format.
+    ///
+    /// Factorio versions before 2.0 don't know the integer node types.
+    /// Integers are written as numbers if [`ModSettings::version`] is older.
+    pub fn to_writer(&self, mut writer: impl Write) -> Result<()> {
+        for part in self.version {
+            writer.write_all(&part.to_le_bytes())?;
+        }
+        writer.write_all(&[0])?;
+
+        let integers = self.version[0] >= 2;
+        let section = |settings: &BTreeMap<String, PropertyTree>| {
+            PropertyTree::Dictionary(
+                settings
+                    .iter()
+                    .map(|(name, value)| {
+                        let value = if integers { value.clone() } else { value.without_integers() };
+                        (
+                            name.clone(),
+                            PropertyTree::Dictionary(BTreeMap::from([("value".into(), value)])),
+                        )
+                    })
+                    .collect(),
+            )
+        };
+
+        PropertyTree::Dictionary(BTreeMap::from([
+            (STARTUP.into(), section(&self.startup)),
+            (RUNTIME_GLOBAL.into(), section(&self.runtime_global)),
+            (RUNTIME_PER_USER.into(), section(&self.runtime_per_user)),
+        ]))
+        .write(&mut writer)
+    }
+}
+
+/// Extracts the setting values of one section of the root dictionary. Each
+/// setting is stored as a dictionary with a single "value" entry.
+fn read_section(
+    root: &mut BTreeMap<String, PropertyTree>,
+    name: &str,
+) -> Result<BTreeMap<String, PropertyTree>> {
+    let settings = match root.remove(name) {
+        Some(PropertyTree::Dictionary(settings)) => settings,
+        Some(_) => return Err(invalid(format!("'{name}' is not a dictionary"))),
+        None => return Ok(BTreeMap::new()),
+    };
+
+    settings
+        .into_iter()
+        .map(|(setting, node)| match node {
+            PropertyTree::Dictionary(mut node) => match node.remove("value") {
+                Some(value) => Ok((setting, value)),
+                None => Err(invalid(format!("setting '{setting}' has no value"))),
+            },
+            _ => Err(invalid(format!("setting '{setting}' is not a dictionary"))),
+        })
+        .collect()
+}
+
+impl PropertyTree {
+    /// Reads a property tree in Factorio's binary format.
+    pub fn read(reader: &mut impl Read) -> Result<PropertyTree> {
+        PropertyTree::read_nested(reader, 0)
+    }
+
+    /// Reads a property tree that is nested `depth` lists or dictionaries
+    /// deep, so that corrupt files can't overflow the stack.
+    fn read_nested(reader: &mut impl Read, depth: usize) -> Result<PropertyTree> {
+        if depth > MAX_DEPTH {
+            return Err(invalid(format!("property trees are nested more than {MAX_DEPTH} levels")));
+        }
+
+        let kind = read_u8(reader)?;
+        // The "any type" flag is only relevant inside of Factorio.
+        read_u8(reader)?;
+
+        Ok(match kind {
+            0 => PropertyTree::None,
+            1 => PropertyTree::Bool(read_u8(reader)? != 0),
+            2 => PropertyTree::Number(f64::from_le_bytes(read_bytes(reader)?)),
+            3 => PropertyTree::String(read_string(reader)?),
+            4 => {
+                let mut items = Vec::new();
+                for _ in 0..u32::from_le_bytes(read_bytes(reader)?) {
+                    // List items have keys, which are ignored.
+                    read_string(reader)?;
+                    items.push(PropertyTree::read_nested(reader, depth + 1)?);
+                }
+                PropertyTree::List(items)
+            }
+            5 => {
+                let mut entries = BTreeMap::new();
+                for _ in 0..u32::from_le_bytes(read_bytes(reader)?) {
+                    let key = read_string(reader)?;
+                    entries.insert(key, PropertyTree::read_nested(reader, depth + 1)?);
+                }
+                PropertyTree::Dictionary(entries)
+            }
+            6 => PropertyTree::SignedInteger(i64::from_le_bytes(read_bytes(reader)?)),
+            7 => PropertyTree::UnsignedInteger(u64::from_le_bytes(read_bytes(reader)?)),
+            _ => return Err(invalid(format!("unknown property tree type {kind}"))),
+        })
+    }
+
+    /// Writes the property tree in Factorio's binary format.
+    pub fn write(&self, writer: &mut impl Write) -> Result<()> {
+        let kind: u8 = match self {
+            PropertyTree::None => 0,
+            PropertyTree::Bool(_) => 1,
+            PropertyTree::Number(_) => 2,
+            PropertyTree::String(_) => 3,
+            PropertyTree::List(_) => 4,
+            PropertyTree::Dictionary(_) => 5,
+            PropertyTree::SignedInteger(_) => 6,
+            PropertyTree::UnsignedInteger(_) => 7,
+        };
+        writer.write_all(&[kind, 0])?;
+
+        match self {
+            PropertyTree::None => {}
+            PropertyTree::Bool(value) => writer.write_all(&[u8::from(*value)])?,
+            PropertyTree::Number(value) => writer.write_all(&value.to_le_bytes())?,
+            PropertyTree::String(value) => write_string(writer, value)?,
+            PropertyTree::List(items) => {
+                writer.write_all(&length(items.len())?.to_le_bytes())?;
+                for item in items {
+                    write_string(writer, "")?;
+                    item.write(writer)?;
+                }
+            }
+            PropertyTree::Dictionary(entries) => {
+                writer.write_all(&length(entries.len())?.to_le_bytes())?;
+                for (key, value) in entries {
+                    write_string(writer, key)?;
+                    value.write(writer)?;
+                }
+            }
+            PropertyTree::SignedInteger(value) => writer.write_all(&value.to_le_bytes())?,
+            PropertyTree::UnsignedInteger(value) => writer.write_all(&value.to_le_bytes())?,
+        }
+        Ok(())
+    }
+
+    /// Replaces integer nodes by number nodes, for Factorio versions that
+    /// don't support them.
+    fn without_integers(&self) -> PropertyTree {
+        match self {
+            PropertyTree::SignedInteger(value) => PropertyTree::Number(*value as f64),
+            PropertyTree::UnsignedInteger(value) => PropertyTree::Number(*value as f64),
+            PropertyTree::List(items) => {
+                PropertyTree::List(items.iter().map(PropertyTree::without_integers).collect())
+            }
+            PropertyTree::Dictionary(entries) => PropertyTree::Dictionary(
+                entries.iter().map(|(k, v)| (k.clone(), v.without_integers())).collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+/// The maximum nesting depth of lists and dictionaries in a property tree.
+const MAX_DEPTH: usize = 64;
+
+fn invalid(reason: impl Into<String>) -> FactorioModApiError {
+    FactorioModApiError::InvalidModSettings { reason: reason.into() }
+}
+
+fn read_bytes<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8> {
+    Ok(read_bytes::<1>(reader)?[0])
+}
+
+/// Reads a string, which is preceded by an "empty" flag and, if it isn't
+/// empty, by its length in the space optimized format: a single byte, or 255
+/// followed by a 32-bit length.
+fn read_string(reader: &mut impl Read) -> Result<String> {
+    if read_u8(reader)? != 0 {
+        return Ok(String::new());
+    }
+
+    let len = match read_u8(reader)? {
+        255 => u32::from_le_bytes(read_bytes(reader)?),
+        len => len.into(),
+    };
+
+    let mut bytes = Vec::new();
+    reader.take(len.into()).read_to_end(&mut bytes)?;
+    if bytes.len() != len as usize {
+        return Err(invalid("unexpected end of file"));
+    }
+    String::from_utf8(bytes).map_err(|_| invalid("a string is not valid UTF-8"))
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> Result<()> {
+    if value.is_empty() {
+        writer.write_all(&[1])?;
+        return Ok(());
+    }
+
+    writer.write_all(&[0])?;
+    let len = length(value.len())?;
+    match u8::try_from(len) {
+        Ok(len) if len < 255 => writer.write_all(&[len])?,
+        _ => {
+            writer.write_all(&[255])?;
+            writer.write_all(&len.to_le_bytes())?;
+        }
+    }
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn length(len: usize) -> Result<u32> {
+    u32::try_from(len).map_err(|_| invalid("too many elements"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, error::Error};
+
+    use pretty_assertions::assert_eq;
+
+    use super::{ModSettings, PropertyTree, MAX_DEPTH};
+
+    fn settings(version: [u16; 4]) -> ModSettings {
+        ModSettings {
+            version,
+            startup: BTreeMap::from([
+                ("mymod-enabled".into(), PropertyTree::Bool(true)),
+                ("mymod-count".into(), PropertyTree::SignedInteger(3)),
+                ("mymod-factor".into(), PropertyTree::Number(1.5)),
+            ]),
+            runtime_global: BTreeMap::from([(
+                "mymod-name".into(),
+                PropertyTree::String("x".repeat(300)),
+            )]),
+            runtime_per_user: BTreeMap::from([(
+                "mymod-color".into(),
+                PropertyTree::Dictionary(BTreeMap::from([
+                    ("r".into(), PropertyTree::Number(1.0)),
+                    ("g".into(), PropertyTree::Number(0.0)),
+                ])),
+            )]),
+        }
+    }
+
+    #[test]
+    fn binary_format() -> Result<(), Box<dyn Error>> {
+        let mut bytes = Vec::new();
+        ModSettings {
+            version: [1, 1, 104, 0],
+            startup: BTreeMap::from([("ab".into(), PropertyTree::Bool(true))]),
+            ..Default::default()
+        }
+        .to_writer(&mut bytes)?;
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            1, 0, 1, 0, 104, 0, 0, 0, 0,
+            5, 0, 3, 0, 0, 0,
+            0, 14, b'r', b'u', b'n', b't', b'i', b'm', b'e', b'-', b'g', b'l', b'o', b'b', b'a', b'l',
+            5, 0, 0, 0, 0, 0,
+            0, 16, b'r', b'u', b'n', b't', b'i', b'm', b'e', b'-', b'p', b'e', b'r', b'-', b'u', b's', b'e', b'r',
+            5, 0, 0, 0, 0, 0,
+            0, 7, b's', b't', b'a', b'r', b't', b'u', b'p',
+            5, 0, 1, 0, 0, 0,
+            0, 2, b'a', b'b',
+            5, 0, 1, 0, 0, 0,
+            0, 5, b'v', b'a', b'l', b'u', b'e',
+            1, 0, 1,
+        ];
+        assert_eq!(bytes, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), Box<dyn Error>> {
+        let settings = settings([2, 0, 28, 0]);
+
+        let mut bytes = Vec::new();
+        settings.to_writer(&mut bytes)?;
+        assert_eq!(ModSettings::from_reader(bytes.as_slice())?, settings);
+
+        let json = serde_json::to_string(&settings)?;
+        assert_eq!(serde_json::from_str::<ModSettings>(&json)?, settings);
+        Ok(())
+    }
+
+    #[test]
+    fn integers_before_2_0() -> Result<(), Box<dyn Error>> {
+        let mut bytes = Vec::new();
+        settings([1, 1, 104, 0]).to_writer(&mut bytes)?;
+
+        let settings = ModSettings::from_reader(bytes.as_slice())?;
+        assert_eq!(settings.startup["mymod-count"], PropertyTree::Number(3.0));
+        Ok(())
+    }
+
+    #[test]
+    fn json() -> Result<(), Box<dyn Error>> {
+        let settings: ModSettings = serde_json::from_str(
+            r#"{
+                "version": [2, 0, 28, 0],
+                "startup": { "a": 1, "b": 1.0, "c": "1", "d": [true, null] }
+            }"#,
+        )?;
+
+        assert_eq!(
+            settings.startup,
+            BTreeMap::from([
+                ("a".into(), PropertyTree::SignedInteger(1)),
+                ("b".into(), PropertyTree::Number(1.0)),
+                ("c".into(), PropertyTree::String("1".into())),
+                (
+                    "d".into(),
+                    PropertyTree::List(vec![PropertyTree::Bool(true), PropertyTree::None])
+                ),
+            ])
+        );
+        assert!(settings.runtime_global.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn nesting_limit() -> Result<(), Box<dyn Error>> {
+        let nested = |depth| {
+            let mut tree = PropertyTree::None;
+            for _ in 0..depth {
+                tree = PropertyTree::List(vec![tree]);
+            }
+            let mut bytes = Vec::new();
+            tree.write(&mut bytes).map(|()| bytes)
+        };
+
+        assert!(PropertyTree::read(&mut nested(MAX_DEPTH)?.as_slice()).is_ok());
+        assert!(PropertyTree::read(&mut nested(MAX_DEPTH + 1)?.as_slice()).is_err());
+        Ok(())
+    }
+}
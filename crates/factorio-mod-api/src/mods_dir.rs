@@ -0,0 +1,434 @@
+//! Access to the `mods` directory of a Factorio installation and its
+//! `mod-list.json` file.
+
+use std::{
+    fs::{self, File},
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    api::{FactorioVersion, ModRelease},
+    archive::{read_info_json, ModInfo},
+    resolver::is_builtin,
+    FactorioModApiError, ModPortalClient, Result,
+};
+
+/// Name of the file that records which mods are enabled.
+pub const MOD_LIST: &str = "mod-list.json";
+
+/// The contents of `mod-list.json`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ModList {
+    pub mods: Vec<ModListEntry>,
+}
+
+/// An entry in `mod-list.json`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ModListEntry {
+    pub name: String,
+    pub enabled: bool,
+
+    /// The version to load, if several versions of the mod are installed.
+    /// Factorio loads the newest version otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+}
+
+impl Default for ModList {
+    /// The mod list that Factorio creates for a new installation.
+    fn default() -> Self {
+        ModList { mods: vec![ModListEntry { name: "base".into(), enabled: true, version: None }] }
+    }
+}
+
+impl ModList {
+    pub fn get(&self, name: &str) -> Option<&ModListEntry> {
+        self.mods.iter().find(|entry| entry.name == name)
+    }
+
+    /// Whether a mod is enabled. Factorio enables mods that aren't listed yet.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.get(name).is_none_or(|entry| entry.enabled)
+    }
+
+    /// Enables or disables a mod, adding it to the list if necessary.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        match self.mods.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) => entry.enabled = enabled,
+            None => self.mods.push(ModListEntry { name: name.into(), enabled, version: None }),
+        }
+    }
+
+    /// Removes a mod from the list.
+    pub fn remove(&mut self, name: &str) {
+        self.mods.retain(|entry| entry.name != name);
+    }
+}
+
+/// A mod in a [`ModsDirectory`], either a `.zip` archive or an unpacked
+/// directory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InstalledMod {
+    pub info: ModInfo,
+    pub path: PathBuf,
+}
+
+/// The `mods` directory of a Factorio installation.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use factorio_mod_api::mods_dir::ModsDirectory;
+///
+/// let mods = ModsDirectory::new("/home/user/.factorio/mods");
+/// let mut mod_list = mods.read_mod_list()?;
+/// mod_list.set_enabled("my_mod", false);
+/// mods.write_mod_list(&mod_list)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ModsDirectory {
+    path: PathBuf,
+}
+
+impl ModsDirectory {
+    /// Creates a handle for the mods directory at `path`. The directory is
+    /// created on demand.
+    pub fn new(path: impl Into<PathBuf>) -> ModsDirectory {
+        ModsDirectory { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads `mod-list.json`. Returns the default list if the file doesn't
+    /// exist yet.
+    pub fn read_mod_list(&self) -> Result<ModList> {
+        match File::open(self.path.join(MOD_LIST)) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(ModList::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes `mod-list.json`.
+    pub fn write_mod_list(&self, mod_list: &ModList) -> Result<()> {
+        fs::create_dir_all(&self.path)?;
+        fs::write(self.path.join(MOD_LIST), serde_json::to_string_pretty(mod_list)?)?;
+        Ok(())
+    }
+
+    /// Lists all installed mods, sorted by name and version. Entries that
+    /// aren't valid mods are skipped.
+    pub fn installed_mods(&self) -> Result<Vec<InstalledMod>> {
+        let entries = match fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut mods = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_dir() && path.extension().is_none_or(|ext| ext != "zip") {
+                continue;
+            }
+
+            match read_info_json(&path) {
+                Ok(info) => mods.push(InstalledMod { info, path }),
+                Err(e) => warn!("ignoring {}: {e}", path.display()),
+            }
+        }
+
+        mods.sort_by(|a, b| (&a.info.name, &a.info.version).cmp(&(&b.info.name, &b.info.version)));
+        Ok(mods)
+    }
+
+    /// The installed versions of a mod, oldest first.
+    pub fn installed_versions(&self, name: &str) -> Result<Vec<InstalledMod>> {
+        let mut mods = self.installed_mods()?;
+        mods.retain(|m| m.info.name == name);
+        Ok(mods)
+    }
+
+    /// Copies a mod archive into the directory. An existing file with the
+    /// same name is replaced.
+    pub fn install_archive(&self, archive: &Path) -> Result<InstalledMod> {
+        let info = read_info_json(archive)?;
+        let path = self.archive_path(archive)?;
+
+        if clear_target(archive, &path)? {
+            debug!("copying {} to {}", archive.display(), path.display());
+            fs::create_dir_all(&self.path)?;
+            fs::copy(archive, &path)?;
+        }
+        Ok(InstalledMod { info, path })
+    }
+
+    /// Makes a mod available in the directory without copying it, using a
+    /// symbolic link where supported. The mod is either a `.zip` archive or
+    /// an unpacked mod directory, which is linked as `<name>_<version>` like
+    /// Factorio expects. An existing mod with the same name is replaced.
+    pub fn link_mod(&self, path: &Path) -> Result<PathBuf> {
+        let target = if path.is_dir() {
+            let info = read_info_json(path)?;
+            self.path.join(format!("{}_{}", info.name, info.version))
+        } else if path.is_file() {
+            self.archive_path(path)?
+        } else {
+            return Err(FactorioModApiError::InvalidModArchive {
+                path: path.into(),
+                reason: "not a file or directory".into(),
+            });
+        };
+
+        if clear_target(path, &target)? {
+            fs::create_dir_all(&self.path)?;
+            link_or_copy(path, &target)?;
+        }
+        Ok(target)
+    }
+
+    /// Deletes all installed versions of a mod. Returns the deleted paths.
+    pub fn remove(&self, name: &str) -> Result<Vec<PathBuf>> {
+        let mut removed = Vec::new();
+        for installed in self.installed_versions(name)? {
+            debug!("removing {}", installed.path.display());
+            if installed.path.is_dir() {
+                fs::remove_dir_all(&installed.path)?;
+            } else {
+                fs::remove_file(&installed.path)?;
+            }
+            removed.push(installed.path);
+        }
+        Ok(removed)
+    }
+
+    fn archive_path(&self, archive: &Path) -> Result<PathBuf> {
+        let file_name =
+            archive.file_name().ok_or_else(|| FactorioModApiError::InvalidModArchive {
+                path: archive.into(),
+                reason: "not a file".into(),
+            })?;
+        Ok(self.path.join(file_name))
+    }
+}
+
+/// Removes the file, link or directory at `target`, so that `source` can be
+/// installed there. Returns `false` if `target` already is `source`.
+fn clear_target(source: &Path, target: &Path) -> Result<bool> {
+    let Ok(metadata) = fs::symlink_metadata(target) else { return Ok(true) };
+    if fs::canonicalize(target).ok() == Some(fs::canonicalize(source)?) {
+        debug!("{} is already installed", source.display());
+        return Ok(false);
+    }
+
+    warn!("replacing {}", target.display());
+    if metadata.is_dir() {
+        fs::remove_dir_all(target)?;
+    } else {
+        fs::remove_file(target)?;
+    }
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn link_or_copy(from: &Path, to: &Path) -> Result<()> {
+    let from = fs::canonicalize(from)?;
+    debug!("create symbolic link: {} -> {} ", from.display(), to.display());
+
+    std::os::unix::fs::symlink(from, to)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn link_or_copy(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            link_or_copy(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+impl ModPortalClient {
+    /// Finds installed mods for which the mod portal has a newer release. If
+    /// several versions of a mod are installed, the newest one is compared.
+    /// Mods that the mod portal doesn't know are skipped.
+    ///
+    /// If `factorio_version` is given, only releases made for that version of
+    /// the game are considered.
+    pub async fn outdated_mods(
+        &self,
+        mods: &ModsDirectory,
+        factorio_version: Option<FactorioVersion>,
+    ) -> Result<Vec<(InstalledMod, &ModRelease)>> {
+        let mut installed = mods.installed_mods()?;
+
+        // Keep only the newest version of each mod.
+        installed.reverse();
+        installed.dedup_by(|a, b| a.info.name == b.info.name);
+        installed.reverse();
+
+        let mut outdated = Vec::new();
+        for installed in installed.into_iter().filter(|m| !is_builtin(&m.info.name)) {
+            let latest = match self.get_latest_release(&installed.info.name, factorio_version).await
+            {
+                Ok(latest) => latest,
+                Err(
+                    FactorioModApiError::NotFound { .. }
+                    | FactorioModApiError::NoMatchingRelease { .. },
+                ) => {
+                    debug!("no newer release found for '{}'", installed.info.name);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            if latest.version > installed.info.version {
+                outdated.push((installed, latest));
+            }
+        }
+        Ok(outdated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, fs};
+
+    use httpmock::prelude::*;
+    use semver::Version;
+
+    use super::{ModList, ModListEntry, ModsDirectory};
+    use crate::{archive::tests::write_mod_archive, tests::setup};
+
+    fn info_json(version: &str) -> String {
+        format!(
+            r#"{{ "name": "mymod", "version": "{version}", "title": "My Mod", "author": "someone" }}"#
+        )
+    }
+
+    #[test]
+    fn mod_list() -> Result<(), Box<dyn Error>> {
+        let dir = tempfile::tempdir()?;
+        let mods = ModsDirectory::new(dir.path());
+        assert_eq!(mods.read_mod_list()?, ModList::default());
+
+        let mut mod_list = mods.read_mod_list()?;
+        mod_list.set_enabled("mymod", false);
+        mod_list.set_enabled("base", true);
+        mods.write_mod_list(&mod_list)?;
+
+        let mod_list = mods.read_mod_list()?;
+        assert_eq!(
+            mod_list.mods,
+            [
+                ModListEntry { name: "base".into(), enabled: true, version: None },
+                ModListEntry { name: "mymod".into(), enabled: false, version: None },
+            ]
+        );
+        assert!(!mod_list.is_enabled("mymod"));
+        assert!(mod_list.is_enabled("othermod"));
+        Ok(())
+    }
+
+    #[test]
+    fn install_and_remove() -> Result<(), Box<dyn Error>> {
+        let source = tempfile::tempdir()?;
+        let dir = tempfile::tempdir()?;
+        let mods = ModsDirectory::new(dir.path().join("mods"));
+
+        for version in ["1.0.0", "1.1.0"] {
+            let archive = source.path().join(format!("mymod_{version}.zip"));
+            write_mod_archive(&archive, &info_json(version))?;
+            mods.install_archive(&archive)?;
+        }
+        fs::create_dir(mods.path().join("not-a-mod"))?;
+
+        let installed = mods.installed_mods()?;
+        assert_eq!(
+            installed.iter().map(|m| m.info.version.clone()).collect::<Vec<_>>(),
+            [Version::new(1, 0, 0), Version::new(1, 1, 0)]
+        );
+
+        assert_eq!(mods.remove("mymod")?.len(), 2);
+        assert_eq!(mods.installed_mods()?, []);
+        Ok(())
+    }
+
+    #[test]
+    fn link_unpacked_mod() -> Result<(), Box<dyn Error>> {
+        let source = tempfile::tempdir()?;
+        fs::write(source.path().join("info.json"), info_json("0.1.0"))?;
+        let dir = tempfile::tempdir()?;
+        let mods = ModsDirectory::new(dir.path());
+
+        let path = mods.link_mod(source.path())?;
+        assert_eq!(path, dir.path().join("mymod_0.1.0"));
+        assert_eq!(mods.installed_mods()?[0].info.version, Version::new(0, 1, 0));
+
+        assert!(mods.link_mod(dir.path().join("missing").as_path()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn replace_existing_mod() -> Result<(), Box<dyn Error>> {
+        let source = tempfile::tempdir()?;
+        let archive = source.path().join("mymod_0.1.0.zip");
+        write_mod_archive(&archive, &info_json("0.1.0"))?;
+        let unpacked = source.path().join("unpacked");
+        fs::create_dir(&unpacked)?;
+        fs::write(unpacked.join("info.json"), info_json("0.1.0"))?;
+        let dir = tempfile::tempdir()?;
+        let mods = ModsDirectory::new(dir.path());
+
+        mods.install_archive(&archive)?;
+        let installed = mods.install_archive(&archive)?;
+        assert_eq!(mods.link_mod(&archive)?, installed.path);
+
+        // Linking a mod onto itself keeps it.
+        mods.link_mod(&installed.path)?;
+        assert!(installed.path.exists());
+
+        fs::create_dir(dir.path().join("mymod_0.1.0"))?;
+        mods.link_mod(&unpacked)?;
+        mods.link_mod(&unpacked)?;
+        assert_eq!(mods.installed_mods()?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn outdated_mods() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(include_bytes!("tests/short.json"));
+        });
+        let dir = tempfile::tempdir()?;
+        let mods = ModsDirectory::new(dir.path());
+        write_mod_archive(&dir.path().join("mymod_0.0.1.zip"), &info_json("0.0.1"))?;
+
+        let outdated = client.outdated_mods(&mods, None).await?;
+        assert_eq!(outdated.len(), 1);
+        assert_eq!(outdated[0].1.version, Version::new(0, 0, 2));
+
+        write_mod_archive(&dir.path().join("mymod_0.0.2.zip"), &info_json("0.0.2"))?;
+        assert!(client.outdated_mods(&mods, None).await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,569 @@
+//! Publishing APIs of the mod portal. See
+//! <https://wiki.factorio.com/Mod_upload_API>.
+
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use reqwest::{
+    multipart::{Form, Part},
+    RequestBuilder, Url,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    api::{
+        ApiKey, EditImagesResponse, InitUploadResponse, ModDetails, ModImage, NewModDetails,
+        PublishErrorKind, PublishResponse, SuccessResponse,
+    },
+    archive::read_info_json,
+    check_server_status, FactorioModApiError, ModPortalClient, Result,
+};
+
+impl ModPortalClient {
+    /// Upload a new release of an existing mod to the mod portal.
+    ///
+    /// This needs an [`ApiKey`] with the "ModPortal: Upload Mods" permission.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::{api::ApiKey, ModPortalClient};
+    /// use std::path::Path;
+    ///
+    /// let client = ModPortalClient::new()?;
+    /// let api_key = ApiKey("my_api_key".into());
+    /// client.upload_release("my_mod", Path::new("my_mod_1.0.1.zip"), &api_key).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn upload_release(
+        &self,
+        mod_name: &str,
+        archive: &Path,
+        api_key: &ApiKey,
+    ) -> Result<()> {
+        info!("initializing upload of {} for '{mod_name}'", archive.display());
+        let url = self.mod_api_base.join("api/v2/mods/releases/init_upload")?;
+        let form = Form::new().text("mod", mod_name.to_owned());
+        let InitUploadResponse { upload_url } = self.post_form(url, form, api_key).await?;
+
+        info!("uploading {}", archive.display());
+        let form = Form::new().part("file", file_part(archive, "application/zip")?);
+        check_success(self.post_upload(upload_url, form).await?)?;
+
+        Ok(())
+    }
+
+    /// Publish a mod that doesn't exist on the mod portal yet, using its first
+    /// release. The name of the mod is read from the archive's `info.json`.
+    /// Returns the URL of the new mod's page.
+    ///
+    /// This needs an [`ApiKey`] with the "ModPortal: Publish Mods" permission.
+    /// Use [`ModPortalClient::upload_release`] for later releases.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::{api::{ApiKey, NewModDetails}, ModPortalClient};
+    /// use std::path::Path;
+    ///
+    /// let client = ModPortalClient::new()?;
+    /// let details = NewModDetails { license: Some("default_mit".into()), ..Default::default() };
+    /// let url = client
+    ///     .publish_new_mod(Path::new("my_mod_0.1.0.zip"), &details, &ApiKey("my_api_key".into()))
+    ///     .await?;
+    /// println!("published at {url}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_new_mod(
+        &self,
+        archive: &Path,
+        details: &NewModDetails,
+        api_key: &ApiKey,
+    ) -> Result<Url> {
+        let mod_name = read_info_json(archive)?.name;
+
+        info!("initializing publication of new mod '{mod_name}'");
+        let url = self.mod_api_base.join("api/v2/mods/init_publish")?;
+        let form = Form::new().text("mod", mod_name.clone());
+        let InitUploadResponse { upload_url } = self.post_form(url, form, api_key).await?;
+
+        info!("uploading {}", archive.display());
+        let form = to_form(details)?.part("file", file_part(archive, "application/zip")?);
+        let SuccessResponse { url, .. } = check_success(self.post_upload(upload_url, form).await?)?;
+
+        let path = url.unwrap_or_else(|| format!("/mod/{mod_name}"));
+        Ok(self.mod_api_base.join(&path)?)
+    }
+
+    /// Edit the details of a mod on the mod portal. Only the fields of
+    /// `details` that are set are changed.
+    ///
+    /// This needs an [`ApiKey`] with the "ModPortal: Edit Mods" permission.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::{api::{ApiKey, ModDetails}, ModPortalClient};
+    ///
+    /// let client = ModPortalClient::new()?;
+    /// let details = ModDetails { summary: Some("A better summary".into()), ..Default::default() };
+    /// client.edit_details("my_mod", &details, &ApiKey("my_api_key".into())).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn edit_details(
+        &self,
+        mod_name: &str,
+        details: &ModDetails,
+        api_key: &ApiKey,
+    ) -> Result<()> {
+        info!("editing details of '{mod_name}'");
+        let url = self.mod_api_base.join("api/v2/mods/edit_details")?;
+        let form = to_form(details)?.text("mod", mod_name.to_owned());
+        check_success(self.post_form(url, form, api_key).await?)?;
+        Ok(())
+    }
+
+    /// Add an image to the gallery of a mod on the mod portal. Supported
+    /// formats are PNG, JPEG and GIF.
+    ///
+    /// This needs an [`ApiKey`] with the "ModPortal: Edit Mods" permission.
+    /// The new image isn't shown on the mod page until it's included in a call
+    /// to [`ModPortalClient::edit_images`].
+    pub async fn add_image(
+        &self,
+        mod_name: &str,
+        image: &Path,
+        api_key: &ApiKey,
+    ) -> Result<ModImage> {
+        info!("adding image {} to '{mod_name}'", image.display());
+        let url = self.mod_api_base.join("api/v2/mods/images/add")?;
+        let form = Form::new().text("mod", mod_name.to_owned());
+        let InitUploadResponse { upload_url } = self.post_form(url, form, api_key).await?;
+
+        let form = Form::new().part("image", file_part(image, image_mime_type(image))?);
+        self.post_upload(upload_url, form).await
+    }
+
+    /// Set the images shown on the page of a mod, in the given order. Images
+    /// are identified by their [`ModImage::id`].
+    ///
+    /// This needs an [`ApiKey`] with the "ModPortal: Edit Mods" permission.
+    pub async fn edit_images(
+        &self,
+        mod_name: &str,
+        image_ids: &[String],
+        api_key: &ApiKey,
+    ) -> Result<Vec<ModImage>> {
+        info!("editing images of '{mod_name}'");
+        let url = self.mod_api_base.join("api/v2/mods/images/edit")?;
+        let form = Form::new().text("mod", mod_name.to_owned()).text("images", image_ids.join(","));
+        let response: EditImagesResponse = self.post_form(url, form, api_key).await?;
+        if !response.success {
+            return Err(unsuccessful());
+        }
+        Ok(response.images)
+    }
+
+    /// Sends an authenticated multipart request to one of the publishing
+    /// endpoints and parses the response.
+    pub(crate) async fn post_form<T: DeserializeOwned>(
+        &self,
+        url: Url,
+        form: Form,
+        api_key: &ApiKey,
+    ) -> Result<T> {
+        self.send_form(self.client.post(url).bearer_auth(&api_key.0).multipart(form)).await
+    }
+
+    /// Sends a multipart request to an upload URL returned by one of the
+    /// publishing endpoints. These URLs are already authorized, so the API key
+    /// isn't sent along.
+    async fn post_upload<T: DeserializeOwned>(&self, upload_url: Url, form: Form) -> Result<T> {
+        self.send_form(self.client.post(upload_url).multipart(form)).await
+    }
+
+    async fn send_form<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let (response, _permit) = self.send(request).await?;
+        let response = check_server_status(response)?;
+
+        match response.json().await? {
+            PublishResponse::Success(response) => Ok(response),
+            PublishResponse::Error { error, message } => {
+                Err(FactorioModApiError::PublishError { kind: error, message })
+            }
+        }
+    }
+}
+
+/// Turns a response that doesn't report success into an error.
+fn check_success(response: SuccessResponse) -> Result<SuccessResponse> {
+    if response.success {
+        Ok(response)
+    } else {
+        Err(unsuccessful())
+    }
+}
+
+fn unsuccessful() -> FactorioModApiError {
+    FactorioModApiError::PublishError {
+        kind: PublishErrorKind::Unknown,
+        message: Some("the mod portal didn't report success".into()),
+    }
+}
+
+/// Creates a multipart form part containing a file.
+pub(crate) fn file_part(path: &Path, mime_type: &str) -> Result<Part> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, format!("{} is not a file", path.display()))
+        })?
+        .to_string_lossy()
+        .into_owned();
+    Ok(Part::bytes(fs::read(path)?).file_name(file_name).mime_str(mime_type)?)
+}
+
+fn image_mime_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        _ => "image/png",
+    }
+}
+
+/// Converts a flat serializable struct into a multipart form. Lists are sent
+/// as repeated fields.
+fn to_form(value: &impl Serialize) -> Result<Form> {
+    let Value::Object(fields) = serde_json::to_value(value)? else {
+        unreachable!("only structs are converted into forms")
+    };
+
+    fn to_text(value: Value) -> String {
+        match value {
+            Value::String(s) => s,
+            other => other.to_string(),
+        }
+    }
+
+    let mut form = Form::new();
+    for (name, value) in fields {
+        match value {
+            Value::Null => {}
+            Value::Array(values) => {
+                for value in values {
+                    form = form.text(name.clone(), to_text(value));
+                }
+            }
+            value => form = form.text(name, to_text(value)),
+        }
+    }
+    Ok(form)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error, io::ErrorKind, path::Path};
+
+    use httpmock::prelude::*;
+
+    use super::file_part;
+
+    use crate::{
+        api::{ApiKey, ModDetails, NewModDetails, PublishErrorKind},
+        archive::tests::write_mod_archive,
+        tests::setup,
+        FactorioModApiError,
+    };
+
+    /// Matches requests that don't send the API key.
+    fn without_api_key(request: &HttpMockRequest) -> bool {
+        let headers = request.headers.iter().flatten();
+        headers.into_iter().all(|(name, _)| !name.eq_ignore_ascii_case("authorization"))
+    }
+
+    #[tokio::test]
+    async fn upload_release() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let dir = tempfile::tempdir()?;
+        let archive = dir.path().join("mymod_1.2.3.zip");
+        std::fs::write(&archive, b"PK zip contents")?;
+
+        let init = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v2/mods/releases/init_upload")
+                .header("authorization", "Bearer KEY")
+                .body_contains("name=\"mod\"\r\n\r\nmymod");
+            then.status(200).json_body(serde_json::json!({
+                "upload_url": server.url("/upload/mymod/abc")
+            }));
+        });
+        let upload = server.mock(|when, then| {
+            when.method(POST)
+                .path("/upload/mymod/abc")
+                .matches(without_api_key)
+                .body_contains("filename=\"mymod_1.2.3.zip\"")
+                .body_contains("PK zip contents");
+            then.status(200).json_body(serde_json::json!({ "success": true }));
+        });
+
+        client.upload_release("mymod", &archive, &ApiKey("KEY".into())).await?;
+
+        init.assert();
+        upload.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upload_release_unsuccessful() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let dir = tempfile::tempdir()?;
+        let archive = dir.path().join("mymod_1.2.3.zip");
+        std::fs::write(&archive, b"PK zip contents")?;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/api/v2/mods/releases/init_upload");
+            then.status(200).json_body(serde_json::json!({
+                "upload_url": server.url("/upload/mymod/abc")
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/upload/mymod/abc");
+            then.status(200).json_body(serde_json::json!({ "success": false }));
+        });
+
+        let result = client.upload_release("mymod", &archive, &ApiKey("KEY".into())).await;
+
+        assert!(matches!(
+            result,
+            Err(FactorioModApiError::PublishError { kind: PublishErrorKind::Unknown, .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn upload_release_invalid_key() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let dir = tempfile::tempdir()?;
+        let archive = dir.path().join("mymod_1.2.3.zip");
+        std::fs::write(&archive, b"PK zip contents")?;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/api/v2/mods/releases/init_upload");
+            then.status(403).json_body(serde_json::json!({
+                "error": "InvalidApiKey",
+                "message": "Missing or invalid API key for the current endpoint"
+            }));
+        });
+
+        let result = client.upload_release("mymod", &archive, &ApiKey("WRONG".into())).await;
+
+        assert!(matches!(
+            result,
+            Err(FactorioModApiError::PublishError { kind: PublishErrorKind::InvalidApiKey, .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn edit_details() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v2/mods/edit_details")
+                .header("authorization", "Bearer KEY")
+                .body_contains("name=\"mod\"\r\n\r\nmymod")
+                .body_contains("name=\"summary\"\r\n\r\nNew summary")
+                .body_contains("name=\"tags\"\r\n\r\ncombat")
+                .body_contains("name=\"tags\"\r\n\r\nenemies")
+                .body_contains("name=\"deprecated\"\r\n\r\ntrue");
+            then.status(200).json_body(serde_json::json!({
+                "success": true,
+                "url": "/mod/mymod"
+            }));
+        });
+
+        let details = ModDetails {
+            summary: Some("New summary".into()),
+            tags: Some(vec!["combat".into(), "enemies".into()]),
+            deprecated: Some(true),
+            ..Default::default()
+        };
+        client.edit_details("mymod", &details, &ApiKey("KEY".into())).await?;
+
+        mock.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn add_and_order_images() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let dir = tempfile::tempdir()?;
+        let image = dir.path().join("screenshot.png");
+        std::fs::write(&image, b"PNG image")?;
+
+        let init = server.mock(|when, then| {
+            when.method(POST).path("/api/v2/mods/images/add");
+            then.status(200).json_body(serde_json::json!({
+                "upload_url": server.url("/upload/image/abc")
+            }));
+        });
+        let upload = server.mock(|when, then| {
+            when.method(POST)
+                .path("/upload/image/abc")
+                .matches(without_api_key)
+                .body_contains("filename=\"screenshot.png\"")
+                .body_contains("image/png");
+            then.status(200).json_body(serde_json::json!({
+                "id": "f00",
+                "url": "https://assets-mod.factorio.com/assets/f00.png",
+                "thumbnail": "https://assets-mod.factorio.com/assets/f00.thumb.png"
+            }));
+        });
+        let edit = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v2/mods/images/edit")
+                .body_contains("name=\"images\"\r\n\r\nf00,ba5");
+            then.status(200).json_body(serde_json::json!({
+                "success": true,
+                "images": []
+            }));
+        });
+
+        let api_key = ApiKey("KEY".into());
+        let added = client.add_image("mymod", &image, &api_key).await?;
+        assert_eq!(added.id, "f00");
+        client.edit_images("mymod", &[added.id, "ba5".into()], &api_key).await?;
+
+        init.assert();
+        upload.assert();
+        edit.assert();
+        Ok(())
+    }
+
+    #[test]
+    fn file_part_needs_file_name() {
+        assert!(matches!(
+            file_part(Path::new("/"), "image/png"),
+            Err(FactorioModApiError::IOError(e)) if e.kind() == ErrorKind::InvalidInput
+        ));
+    }
+
+    #[tokio::test]
+    async fn publish_new_mod() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let dir = tempfile::tempdir()?;
+        let archive = dir.path().join("mymod_0.1.0.zip");
+        write_mod_archive(
+            &archive,
+            r#"{"name": "mymod", "version": "0.1.0", "title": "My Mod", "author": "someone"}"#,
+        )?;
+
+        let init = server.mock(|when, then| {
+            when.method(POST)
+                .path("/api/v2/mods/init_publish")
+                .header("authorization", "Bearer KEY")
+                .body_contains("name=\"mod\"\r\n\r\nmymod");
+            then.status(200).json_body(serde_json::json!({
+                "upload_url": server.url("/upload/new/abc")
+            }));
+        });
+        let upload = server.mock(|when, then| {
+            when.method(POST)
+                .path("/upload/new/abc")
+                .matches(without_api_key)
+                .body_contains("name=\"license\"\r\n\r\ndefault_mit")
+                .body_contains("filename=\"mymod_0.1.0.zip\"");
+            then.status(200).json_body(serde_json::json!({
+                "success": true,
+                "url": "/mod/mymod"
+            }));
+        });
+
+        let details = NewModDetails { license: Some("default_mit".into()), ..Default::default() };
+        let url = client.publish_new_mod(&archive, &details, &ApiKey("KEY".into())).await?;
+
+        assert_eq!(url.path(), "/mod/mymod");
+        init.assert();
+        upload.assert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn publish_new_mod_invalid_upload() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let dir = tempfile::tempdir()?;
+        let archive = dir.path().join("mymod_0.1.0.zip");
+        write_mod_archive(
+            &archive,
+            r#"{"name": "mymod", "version": "0.1.0", "title": "My Mod", "author": "someone"}"#,
+        )?;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/api/v2/mods/init_publish");
+            then.status(200).json_body(serde_json::json!({
+                "upload_url": server.url("/upload/new/abc")
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/upload/new/abc");
+            then.status(400).json_body(serde_json::json!({
+                "error": "InvalidModUpload",
+                "message": "Mod already exists"
+            }));
+        });
+
+        let result =
+            client.publish_new_mod(&archive, &Default::default(), &ApiKey("KEY".into())).await;
+
+        assert!(matches!(
+            result,
+            Err(FactorioModApiError::PublishError {
+                kind: PublishErrorKind::InvalidModUpload,
+                message: Some(_)
+            })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn publish_new_mod_invalid_url() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let dir = tempfile::tempdir()?;
+        let archive = dir.path().join("mymod_0.1.0.zip");
+        write_mod_archive(
+            &archive,
+            r#"{"name": "mymod", "version": "0.1.0", "title": "My Mod", "author": "someone"}"#,
+        )?;
+
+        server.mock(|when, then| {
+            when.method(POST).path("/api/v2/mods/init_publish");
+            then.status(200).json_body(serde_json::json!({
+                "upload_url": server.url("/upload/new/abc")
+            }));
+        });
+        server.mock(|when, then| {
+            when.method(POST).path("/upload/new/abc");
+            then.status(200).json_body(serde_json::json!({
+                "success": true,
+                "url": "https://[oops/"
+            }));
+        });
+
+        let result =
+            client.publish_new_mod(&archive, &Default::default(), &ApiKey("KEY".into())).await;
+
+        assert!(matches!(result, Err(FactorioModApiError::UrlError(_))));
+        Ok(())
+    }
+}
@@ -0,0 +1,850 @@
+//! Dependency resolution for sets of mods.
+//!
+//! The resolver does a depth-first search over the releases of all mods that
+//! are (transitively) required, trying newer versions first. When a choice
+//! leads to a conflict, it backtracks and tries older versions, so that a
+//! solution is found whenever one exists.
+//!
+//! All [`ModDependencyFlavor`]s are honored:
+//!
+//! * Required dependencies (`Normal` and `NoEffectOnLoadOrder`) are added to
+//!   the solution and their version constraints must hold.
+//! * Optional dependencies (`Optional` and `Hidden`) are not added to the
+//!   solution, but if the mod is part of it anyway, their version constraints
+//!   must hold.
+//! * `Incompatibility` excludes the mod from the solution.
+//!
+//! If a target Factorio version is given in the [`ResolveOptions`], only
+//! releases made for that version are considered.
+//!
+//! Built-in mods ([`BUILTIN_MODS`]) are never looked up. If the target version
+//! is known, dependencies on them are checked against the mods that are part
+//! of that version of the game. `base` is always enabled, the expansion mods
+//! only if something requires them. Without a target version, they are
+//! assumed to hold and reported in [`Resolution::unchecked_builtins`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use itertools::Itertools;
+use semver::{Comparator, Op, Version};
+use tracing::{debug, trace, warn};
+
+use crate::{
+    api::{FactorioVersion, ModDependency, ModDependencyFlavor, ModRelease},
+    FactorioModApiError, ModPortalClient, Result,
+};
+
+/// A source of mod releases for the resolver.
+pub trait ReleaseProvider {
+    /// Returns all releases of a mod, or `None` if the mod is unknown.
+    fn releases(&self, name: &str) -> Option<&[ModRelease]>;
+}
+
+impl ReleaseProvider for HashMap<String, Vec<ModRelease>> {
+    fn releases(&self, name: &str) -> Option<&[ModRelease]> {
+        self.get(name).map(Vec::as_slice)
+    }
+}
+
+impl ReleaseProvider for BTreeMap<String, Vec<ModRelease>> {
+    fn releases(&self, name: &str) -> Option<&[ModRelease]> {
+        self.get(name).map(Vec::as_slice)
+    }
+}
+
+/// Mods that are part of the game, with the first version of Factorio that
+/// ships them. Their versions are always the same as the game's.
+pub const BUILTIN_MODS: &[(&str, FactorioVersion)] = &[
+    ("base", FactorioVersion::new(0, 0)),
+    ("elevated-rails", FactorioVersion::new(2, 0)),
+    ("quality", FactorioVersion::new(2, 0)),
+    ("space-age", FactorioVersion::new(2, 0)),
+];
+
+/// Whether a mod is part of the game and can't be downloaded from the mod
+/// portal.
+pub fn is_builtin(name: &str) -> bool {
+    BUILTIN_MODS.iter().any(|(builtin, _)| *builtin == name)
+}
+
+/// Whether a built-in mod is part of a version of the game.
+fn is_part_of(name: &str, game: FactorioVersion) -> bool {
+    BUILTIN_MODS.iter().any(|(builtin, since)| *builtin == name && *since <= game)
+}
+
+/// Settings that apply to a whole resolution.
+#[derive(Clone, Debug, Default)]
+pub struct ResolveOptions {
+    /// The version of the game that the mods are resolved for. If set, only
+    /// releases compatible with this version are considered.
+    pub factorio_version: Option<FactorioVersion>,
+
+    /// The exact version of the game, if known. Version requirements on
+    /// built-in mods are checked against it. Without it, they only need to be
+    /// satisfiable by some release of `factorio_version`.
+    pub game_version: Option<Version>,
+}
+
+impl ResolveOptions {
+    /// Options for resolving mods for a specific release of the game.
+    pub fn for_game_version(version: &Version) -> ResolveOptions {
+        ResolveOptions {
+            factorio_version: Some(version.into()),
+            game_version: Some(version.clone()),
+        }
+    }
+
+    /// The targeted major version of the game, if known.
+    fn target(&self) -> Option<FactorioVersion> {
+        self.factorio_version.or(self.game_version.as_ref().map(FactorioVersion::from))
+    }
+
+    /// Whether the targeted game provides a version of the built-in mods that
+    /// matches `comparator`.
+    fn provides(&self, comparator: &Comparator) -> bool {
+        match (&self.game_version, self.target()) {
+            (Some(version), _) => comparator.matches(version),
+            (None, Some(FactorioVersion { major, minor })) => {
+                comparator.matches(&Version::new(major, minor, 0))
+                    || comparator.matches(&Version::new(major, minor, u64::MAX))
+                    || (comparator.op == Op::Exact
+                        && comparator.major == major
+                        && comparator.minor == Some(minor))
+            }
+            (None, None) => true,
+        }
+    }
+
+    /// Describes the targeted game.
+    fn describe_game(&self) -> String {
+        match (&self.game_version, self.target()) {
+            (Some(version), _) => format!("Factorio {version}"),
+            (None, Some(target)) => format!("Factorio {target}"),
+            (None, None) => "the game".into(),
+        }
+    }
+}
+
+/// A set of mod releases that satisfies all dependencies.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Resolution {
+    /// The mods that resolution was requested for.
+    pub requested: Vec<ModDependency>,
+
+    /// The selected release for each mod, by mod name.
+    pub releases: BTreeMap<String, ModRelease>,
+
+    /// Requirements on built-in mods that weren't checked, because the
+    /// targeted version of the game is unknown.
+    pub unchecked_builtins: Vec<Requirement>,
+}
+
+impl Resolution {
+    /// The dependencies of a selected mod, or of the requested mod set if
+    /// `name` is `None`. This includes dependencies that aren't part of the
+    /// resolution, like optional dependencies, incompatibilities and built-in
+    /// mods.
+    pub fn dependencies_of(&self, name: Option<&str>) -> &[ModDependency] {
+        match name {
+            Some(name) => self.releases.get(name).map(dependencies).unwrap_or_default(),
+            None => &self.requested,
+        }
+    }
+}
+
+/// A dependency, together with the mod release that declared it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Requirement {
+    /// Name and version of the mod that declared the dependency, or `None` for
+    /// the mods that resolution was requested for.
+    pub required_by: Option<(String, Version)>,
+
+    pub dependency: ModDependency,
+}
+
+impl Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.required_by {
+            Some((name, version)) => write!(f, "'{name} {version}'")?,
+            None => write!(f, "the requested mod set")?,
+        }
+
+        use ModDependencyFlavor::*;
+        match self.dependency.flavor {
+            Incompatibility => write!(f, " is incompatible with '{}'", self.dependency.name),
+            Optional | Hidden => write!(f, " optionally depends on '{}'", dependency(self)),
+            Normal | NoEffectOnLoadOrder => write!(f, " depends on '{}'", dependency(self)),
+        }
+    }
+}
+
+/// Formats a dependency without its flavor prefix.
+fn dependency(requirement: &Requirement) -> ModDependency {
+    ModDependency { flavor: ModDependencyFlavor::Normal, ..requirement.dependency.clone() }
+}
+
+/// Explanation of why no compatible version of a mod could be found.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+    /// The mod for which no version could be selected.
+    pub mod_name: String,
+
+    /// All requirements on the mod at the time of the conflict.
+    pub requirements: Vec<Requirement>,
+
+    /// All versions of the mod known to the mod portal.
+    pub available: Vec<Version>,
+
+    /// Reasons why versions that satisfied the requirements were rejected.
+    pub rejected: Vec<String>,
+}
+
+impl std::error::Error for Conflict {}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.available.is_empty() {
+            write!(f, "no releases of '{}' found", self.mod_name)?;
+        } else {
+            write!(f, "no version of '{}' satisfies all requirements", self.mod_name)?;
+        }
+
+        for requirement in &self.requirements {
+            write!(f, "\n  {requirement}")?;
+        }
+        for reason in &self.rejected {
+            write!(f, "\n  {reason}")?;
+        }
+        if !self.available.is_empty() {
+            write!(f, "\navailable versions: {}", self.available.iter().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds a set of releases for `mods` and all their transitive dependencies
+/// that satisfies all dependency constraints. Newer versions are preferred.
+///
+/// Mods that the provider doesn't know are treated as having no releases.
+pub fn resolve<P: ReleaseProvider + ?Sized>(
+    provider: &P,
+    mods: &[ModDependency],
+    options: &ResolveOptions,
+) -> Result<Resolution> {
+    match (Search { provider, options, fail_on_unknown: false }).run(mods) {
+        Ok(resolution) => Ok(resolution),
+        Err(SearchError::Conflict(conflict)) => Err(conflict.into()),
+        Err(SearchError::Unknown(_)) => unreachable!("unknown mods are treated as empty"),
+    }
+}
+
+impl ModPortalClient {
+    /// Finds a set of releases for `mods` and all their transitive
+    /// dependencies that satisfies all dependency constraints, using the mod
+    /// portal to look up releases. Newer versions are preferred.
+    ///
+    /// See the [`resolver`](crate::resolver) module for details.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// use factorio_mod_api::{resolver::ResolveOptions, ModPortalClient};
+    ///
+    /// let client = ModPortalClient::new()?;
+    /// let options = ResolveOptions::for_game_version(&"1.1.110".parse()?);
+    /// let resolution = client.resolve_mods(&["my_mod >= 1.1.0".try_into()?], &options).await?;
+    /// for (name, release) in resolution.releases {
+    ///     println!("{name} {}", release.version);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn resolve_mods(
+        &self,
+        mods: &[ModDependency],
+        options: &ResolveOptions,
+    ) -> Result<Resolution> {
+        let mut known = HashMap::new();
+        loop {
+            match (Search { provider: &known, options, fail_on_unknown: true }).run(mods) {
+                Ok(resolution) => return Ok(resolution),
+                Err(SearchError::Conflict(conflict)) => return Err(conflict.into()),
+
+                // The search is restarted from scratch after each new mod. This
+                // is cheap compared to the request, and keeps the search
+                // itself synchronous.
+                Err(SearchError::Unknown(name)) => {
+                    let releases = match self.get_mod_spec(&name).await {
+                        Ok(spec) => spec.short_spec.releases.clone(),
+                        Err(FactorioModApiError::NotFound { .. }) => vec![],
+                        Err(e) => return Err(e),
+                    };
+                    known.insert(name, releases);
+                }
+            }
+        }
+    }
+}
+
+enum SearchError {
+    /// The provider doesn't know the releases of a mod.
+    Unknown(String),
+
+    /// No solution exists.
+    Conflict(Conflict),
+}
+
+struct Search<'p, P: ?Sized> {
+    provider: &'p P,
+    options: &'p ResolveOptions,
+
+    /// Whether to abort when a mod is unknown, instead of treating it as
+    /// having no releases.
+    fail_on_unknown: bool,
+}
+
+/// A partial solution.
+#[derive(Clone, Default)]
+struct State<'p> {
+    selected: BTreeMap<String, &'p ModRelease>,
+    requirements: Vec<Requirement>,
+}
+
+impl<'p> State<'p> {
+    /// The first required mod that hasn't been selected yet.
+    fn next_open(&self) -> Option<&str> {
+        self.requirements
+            .iter()
+            .map(|r| &r.dependency)
+            .find(|d| {
+                d.is_required() && !is_builtin(&d.name) && !self.selected.contains_key(&d.name)
+            })
+            .map(|d| d.name.as_str())
+    }
+
+    fn requirements_on(&self, name: &str) -> Vec<Requirement> {
+        self.requirements.iter().filter(|r| r.dependency.name == name).cloned().collect()
+    }
+
+    /// Checks whether a release is compatible with all selected releases.
+    /// Returns the reason if it isn't.
+    fn check_compatible(&self, release: &ModRelease) -> std::result::Result<(), String> {
+        for dep in dependencies(release) {
+            let Some(selected) = self.selected.get(&dep.name) else { continue };
+            let (name, version) = (&dep.name, &selected.version);
+
+            if dep.flavor == ModDependencyFlavor::Incompatibility {
+                return Err(format!(
+                    "version {} is incompatible with '{name} {version}'",
+                    release.version
+                ));
+            }
+            if let Some(comparator) = &dep.comparator {
+                if !comparator.matches(version) {
+                    return Err(format!(
+                        "version {} depends on '{name} {comparator}', but '{name} {version}' is selected",
+                        release.version
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn select(&mut self, name: &str, release: &'p ModRelease, requirements: Vec<Requirement>) {
+        self.requirements.extend(requirements);
+        self.selected.insert(name.into(), release);
+    }
+}
+
+impl<'p, P: ReleaseProvider + ?Sized> Search<'p, P> {
+    fn run(&self, mods: &[ModDependency]) -> std::result::Result<Resolution, SearchError> {
+        let state = State {
+            requirements: mods
+                .iter()
+                .map(|dependency| Requirement { required_by: None, dependency: dependency.clone() })
+                .collect(),
+            ..Default::default()
+        };
+
+        if let Err((name, reason)) = self.check_builtins(&[], &state.requirements) {
+            let available = self.options.target().is_some_and(|t| is_part_of(&name, t));
+            return Err(SearchError::Conflict(Conflict {
+                available: self
+                    .options
+                    .game_version
+                    .iter()
+                    .filter(|_| available)
+                    .cloned()
+                    .collect(),
+                requirements: vec![],
+                rejected: vec![reason],
+                mod_name: name,
+            }));
+        }
+
+        let selected = self.search(state.clone())?;
+        let unchecked_builtins = if self.options.target().is_none() {
+            unchecked_builtins(&state, &selected)
+        } else {
+            vec![]
+        };
+        Ok(Resolution {
+            requested: mods.to_vec(),
+            releases: selected.into_iter().map(|(name, release)| (name, release.clone())).collect(),
+            unchecked_builtins,
+        })
+    }
+
+    fn search(
+        &self,
+        state: State<'p>,
+    ) -> std::result::Result<BTreeMap<String, &'p ModRelease>, SearchError> {
+        let Some(name) = state.next_open() else { return Ok(state.selected) };
+        let name = name.to_owned();
+        trace!("selecting a version of '{name}'");
+
+        let releases = match self.provider.releases(&name) {
+            Some(releases) => releases,
+            None if self.fail_on_unknown => return Err(SearchError::Unknown(name)),
+            None => &[],
+        };
+
+        let requirements = state.requirements_on(&name);
+        let excluded = requirements
+            .iter()
+            .any(|r| r.dependency.flavor == ModDependencyFlavor::Incompatibility);
+
+        let (candidates, incompatible): (Vec<_>, Vec<_>) = releases
+            .iter()
+            .filter(|_| !excluded)
+            .filter(|release| {
+                requirements.iter().all(|r| {
+                    r.dependency.comparator.as_ref().is_none_or(|c| c.matches(&release.version))
+                })
+            })
+            .sorted_by(|a, b| b.version.cmp(&a.version))
+            .partition(|release| self.supports_game_version(release));
+
+        let mut rejected = Vec::new();
+        if let Some(game) = self.options.target().filter(|_| !incompatible.is_empty()) {
+            let versions = incompatible.iter().rev().map(|r| &r.version).join(", ");
+            rejected.push(format!("versions {versions} aren't compatible with Factorio {game}"));
+        }
+
+        let mut first_conflict = None;
+
+        for release in candidates {
+            let new_requirements = requirements_of(&name, release);
+            if let Err(reason) = state.check_compatible(release).and_then(|()| {
+                self.check_builtins(&state.requirements, &new_requirements)
+                    .map_err(|(_, reason)| reason)
+            }) {
+                debug!("rejecting '{name} {}': {reason}", release.version);
+                rejected.push(reason);
+                continue;
+            }
+
+            let mut next = state.clone();
+            next.select(&name, release, new_requirements);
+
+            match self.search(next) {
+                Ok(selected) => return Ok(selected),
+                Err(SearchError::Conflict(conflict)) => {
+                    debug!("backtracking from '{name} {}': {conflict}", release.version);
+                    first_conflict.get_or_insert(conflict);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(SearchError::Conflict(first_conflict.unwrap_or_else(|| Conflict {
+            mod_name: name,
+            requirements,
+            available: releases.iter().map(|r| r.version.clone()).sorted().collect(),
+            rejected,
+        })))
+    }
+
+    /// Checks the requirements on built-in mods that `new` adds to `existing`
+    /// against the targeted game. Returns the affected mod and the reason if
+    /// they can't be satisfied.
+    fn check_builtins(
+        &self,
+        existing: &[Requirement],
+        new: &[Requirement],
+    ) -> std::result::Result<(), (String, String)> {
+        let Some(target) = self.options.target() else { return Ok(()) };
+        let all = || existing.iter().chain(new);
+        let game = self.options.describe_game();
+
+        for name in new.iter().map(|r| &r.dependency.name).filter(|n| is_builtin(n)).unique() {
+            let on_mod = || all().filter(|r| r.dependency.name == *name);
+            let fail = |reason: String| Err((name.clone(), reason));
+
+            // Expansions are only enabled if something requires them.
+            let required_by = on_mod().find(|r| r.dependency.is_required());
+            if name != "base" && required_by.is_none() {
+                continue;
+            }
+
+            if let Some(requirement) = required_by.filter(|_| !is_part_of(name, target)) {
+                return fail(format!("{requirement}, which isn't part of {game}"));
+            }
+
+            for requirement in on_mod() {
+                if requirement.dependency.flavor == ModDependencyFlavor::Incompatibility {
+                    return fail(format!("{requirement}, but '{name}' is enabled"));
+                }
+                if let Some(comparator) = &requirement.dependency.comparator {
+                    if !self.options.provides(comparator) {
+                        return fail(format!("{requirement}, which {game} doesn't provide"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a release is made for the target Factorio version, if any.
+    fn supports_game_version(&self, release: &ModRelease) -> bool {
+        self.options
+            .target()
+            .is_none_or(|game| release.info_json.factorio_version.is_compatible_with(&game))
+    }
+}
+
+/// The requirements on built-in mods that would be checked if the target
+/// version was known.
+fn unchecked_builtins(
+    initial: &State,
+    selected: &BTreeMap<String, &ModRelease>,
+) -> Vec<Requirement> {
+    let requirements = initial
+        .requirements
+        .iter()
+        .cloned()
+        .chain(selected.iter().flat_map(|(name, release)| requirements_of(name, release)));
+
+    let unchecked: Vec<_> = requirements
+        .filter(|r| {
+            let dependency = &r.dependency;
+            is_builtin(&dependency.name)
+                && (dependency.comparator.is_some()
+                    || dependency.flavor == ModDependencyFlavor::Incompatibility
+                    || (dependency.name != "base" && dependency.is_required()))
+        })
+        .collect();
+
+    for requirement in &unchecked {
+        warn!("not checked, because the Factorio version is unknown: {requirement}");
+    }
+    unchecked
+}
+
+/// The requirements that selecting a release adds.
+fn requirements_of(name: &str, release: &ModRelease) -> Vec<Requirement> {
+    dependencies(release)
+        .iter()
+        .map(|dependency| Requirement {
+            required_by: Some((name.into(), release.version.clone())),
+            dependency: dependency.clone(),
+        })
+        .collect()
+}
+
+fn dependencies(release: &ModRelease) -> &[ModDependency] {
+    release.info_json.dependencies.as_deref().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, error::Error};
+
+    use chrono::DateTime;
+    use httpmock::prelude::*;
+    use semver::Version;
+
+    use super::{resolve, Resolution, ResolveOptions};
+    use crate::{
+        api::{FactorioVersion, ModDependency, ModManifest, ModRelease},
+        tests::setup,
+        FactorioModApiError,
+    };
+
+    fn release(name: &str, version: &str, dependencies: &[&str]) -> ModRelease {
+        ModRelease {
+            download_url: format!("/download/{name}/{version}"),
+            file_name: format!("{name}_{version}.zip"),
+            info_json: ModManifest {
+                factorio_version: FactorioVersion::new(1, 1),
+                dependencies: Some(
+                    dependencies.iter().map(|d| ModDependency::try_from(*d).unwrap()).collect(),
+                ),
+            },
+            released_at: DateTime::default(),
+            version: Version::parse(version).unwrap(),
+            sha1: String::new(),
+        }
+    }
+
+    fn provider(releases: Vec<ModRelease>) -> HashMap<String, Vec<ModRelease>> {
+        let mut provider: HashMap<String, Vec<ModRelease>> = HashMap::new();
+        for release in releases {
+            let name = release.file_name.rsplit_once('_').unwrap().0.to_owned();
+            provider.entry(name).or_default().push(release);
+        }
+        provider
+    }
+
+    fn deps(deps: &[&str]) -> Vec<ModDependency> {
+        deps.iter().map(|d| ModDependency::try_from(*d).unwrap()).collect()
+    }
+
+    fn versions(resolution: &Resolution) -> Vec<String> {
+        resolution.releases.iter().map(|(name, r)| format!("{name} {}", r.version)).collect()
+    }
+
+    #[test]
+    fn picks_latest_versions() -> Result<(), Box<dyn Error>> {
+        let provider = provider(vec![
+            release("alpha", "1.0.0", &["base", "bravo >= 1.0.0"]),
+            release("alpha", "1.1.0", &["base", "bravo >= 1.0.0"]),
+            release("bravo", "1.0.0", &[]),
+            release("bravo", "2.0.0", &[]),
+        ]);
+
+        let resolution = resolve(&provider, &deps(&["alpha"]), &ResolveOptions::default())?;
+
+        assert_eq!(versions(&resolution), ["alpha 1.1.0", "bravo 2.0.0"]);
+        assert_eq!(resolution.dependencies_of(None), deps(&["alpha"]));
+        assert_eq!(resolution.dependencies_of(Some("alpha")), deps(&["base", "bravo >= 1.0.0"]));
+        assert_eq!(resolution.dependencies_of(Some("charlie")), []);
+        Ok(())
+    }
+
+    #[test]
+    fn backtracks() -> Result<(), Box<dyn Error>> {
+        let provider = provider(vec![
+            release("alpha", "1.0.0", &["charlie"]),
+            release("alpha", "2.0.0", &["charlie >= 2.0.0"]),
+            release("bravo", "1.0.0", &["charlie < 2.0.0"]),
+            release("charlie", "1.0.0", &[]),
+            release("charlie", "1.5.0", &[]),
+            release("charlie", "2.0.0", &[]),
+        ]);
+
+        let resolution =
+            resolve(&provider, &deps(&["alpha", "bravo"]), &ResolveOptions::default())?;
+
+        assert_eq!(versions(&resolution), ["alpha 1.0.0", "bravo 1.0.0", "charlie 1.5.0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn optional_dependencies() -> Result<(), Box<dyn Error>> {
+        let provider = provider(vec![
+            release("alpha", "1.0.0", &["? bravo >= 1.5.0", "(?) charlie"]),
+            release("bravo", "1.0.0", &[]),
+            release("bravo", "1.5.0", &["~ delta"]),
+            release("bravo", "2.0.0", &[]),
+            release("charlie", "1.0.0", &[]),
+            release("delta", "1.0.0", &[]),
+        ]);
+
+        let resolution = resolve(&provider, &deps(&["alpha"]), &ResolveOptions::default())?;
+        assert_eq!(versions(&resolution), ["alpha 1.0.0"]);
+
+        let resolution =
+            resolve(&provider, &deps(&["alpha", "bravo < 2.0.0"]), &ResolveOptions::default())?;
+        assert_eq!(versions(&resolution), ["alpha 1.0.0", "bravo 1.5.0", "delta 1.0.0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn incompatibilities() -> Result<(), Box<dyn Error>> {
+        let provider = provider(vec![
+            release("alpha", "1.0.0", &[]),
+            release("alpha", "2.0.0", &["! bravo"]),
+            release("bravo", "1.0.0", &[]),
+        ]);
+
+        let resolution =
+            resolve(&provider, &deps(&["alpha", "bravo"]), &ResolveOptions::default())?;
+        assert_eq!(versions(&resolution), ["alpha 1.0.0", "bravo 1.0.0"]);
+
+        let result =
+            resolve(&provider, &deps(&["alpha >= 2.0.0", "bravo"]), &ResolveOptions::default());
+        let Err(FactorioModApiError::ResolutionConflict(conflict)) = result else {
+            panic!("expected a conflict, got {result:?}");
+        };
+        assert_eq!(conflict.mod_name, "bravo");
+        assert_eq!(
+            conflict.to_string(),
+            "no version of 'bravo' satisfies all requirements\n  \
+             the requested mod set depends on 'bravo'\n  \
+             'alpha 2.0.0' is incompatible with 'bravo'\n\
+             available versions: 1.0.0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn explains_conflicts() -> Result<(), Box<dyn Error>> {
+        let provider = provider(vec![
+            release("alpha", "1.0.0", &["charlie >= 2.0.0"]),
+            release("bravo", "1.0.0", &["charlie < 2.0.0"]),
+            release("charlie", "1.0.0", &[]),
+            release("charlie", "2.0.0", &[]),
+        ]);
+
+        let result = resolve(&provider, &deps(&["alpha", "bravo"]), &ResolveOptions::default());
+
+        let Err(FactorioModApiError::ResolutionConflict(conflict)) = result else {
+            panic!("expected a conflict, got {result:?}");
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "no version of 'charlie' satisfies all requirements\n  \
+             'alpha 1.0.0' depends on 'charlie >= 2.0.0'\n  \
+             'bravo 1.0.0' depends on 'charlie < 2.0.0'\n\
+             available versions: 1.0.0, 2.0.0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filters_by_factorio_version() -> Result<(), Box<dyn Error>> {
+        let mut old = release("alpha", "0.5.0", &[]);
+        old.info_json.factorio_version = FactorioVersion::new(0, 18);
+        let mut new = release("alpha", "2.0.0", &[]);
+        new.info_json.factorio_version = FactorioVersion::new(2, 0);
+        let provider = provider(vec![old, release("alpha", "1.0.0", &[]), new]);
+
+        let resolve_for = |factorio_version: Option<FactorioVersion>| {
+            let options = ResolveOptions { factorio_version, ..Default::default() };
+            resolve(&provider, &deps(&["alpha"]), &options)
+        };
+
+        assert_eq!(versions(&resolve_for(None)?), ["alpha 2.0.0"]);
+        assert_eq!(versions(&resolve_for(Some(FactorioVersion::new(1, 1)))?), ["alpha 1.0.0"]);
+        assert_eq!(versions(&resolve_for(Some(FactorioVersion::new(1, 0)))?), ["alpha 0.5.0"]);
+
+        let result = resolve_for(Some(FactorioVersion::new(0, 17)));
+        let Err(FactorioModApiError::ResolutionConflict(conflict)) = result else {
+            panic!("expected a conflict, got {result:?}");
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "no version of 'alpha' satisfies all requirements\n  \
+             the requested mod set depends on 'alpha'\n  \
+             versions 0.5.0, 1.0.0, 2.0.0 aren't compatible with Factorio 0.17\n\
+             available versions: 0.5.0, 1.0.0, 2.0.0"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn builtin_mods() -> Result<(), Box<dyn Error>> {
+        let mut releases = vec![
+            release("alpha", "1.0.0", &["base >= 2.0.0"]),
+            release("alpha", "1.1.0", &["base >= 2.0.20", "space-age"]),
+            release("bravo", "1.0.0", &["! space-age"]),
+            release("charlie", "1.0.0", &["? quality >= 2.0.30"]),
+        ];
+        for release in &mut releases {
+            release.info_json.factorio_version = FactorioVersion::new(2, 0);
+        }
+        let provider = provider(releases);
+
+        let resolve_for = |game: &str, mods: &[&str]| {
+            resolve(&provider, &deps(mods), &ResolveOptions::for_game_version(&game.parse()?))
+        };
+
+        assert_eq!(versions(&resolve_for("2.0.28", &["alpha"])?), ["alpha 1.1.0"]);
+        assert_eq!(versions(&resolve_for("2.0.10", &["alpha"])?), ["alpha 1.0.0"]);
+        assert_eq!(
+            versions(&resolve_for("2.0.28", &["alpha", "bravo"])?),
+            ["alpha 1.0.0", "bravo 1.0.0"]
+        );
+        assert_eq!(versions(&resolve_for("2.0.28", &["charlie"])?), ["charlie 1.0.0"]);
+
+        let options = ResolveOptions {
+            factorio_version: Some(FactorioVersion::new(2, 0)),
+            ..Default::default()
+        };
+        assert_eq!(versions(&resolve(&provider, &deps(&["alpha"]), &options)?), ["alpha 1.1.0"]);
+
+        let result = resolve_for("2.0.28", &["charlie", "quality"]);
+        let Err(FactorioModApiError::ResolutionConflict(conflict)) = result else {
+            panic!("expected a conflict, got {result:?}");
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "no version of 'charlie' satisfies all requirements\n  \
+             the requested mod set depends on 'charlie'\n  \
+             'charlie 1.0.0' optionally depends on 'quality >= 2.0.30', which Factorio 2.0.28 doesn't provide\n\
+             available versions: 1.0.0"
+        );
+
+        let unchecked = resolve(&provider, &deps(&["alpha", "charlie"]), &Default::default())?;
+        assert_eq!(
+            unchecked.unchecked_builtins.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "'alpha 1.1.0' depends on 'base >= 2.0.20'",
+                "'alpha 1.1.0' depends on 'space-age'",
+                "'charlie 1.0.0' optionally depends on 'quality >= 2.0.30'"
+            ]
+        );
+        assert!(resolve_for("2.0.28", &["alpha"])?.unchecked_builtins.is_empty());
+
+        let result = resolve_for("1.1.110", &["space-age"]);
+        let Err(FactorioModApiError::ResolutionConflict(conflict)) = result else {
+            panic!("expected a conflict, got {result:?}");
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "no releases of 'space-age' found\n  \
+             the requested mod set depends on 'space-age', which isn't part of Factorio 1.1.110"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_mod() -> Result<(), Box<dyn Error>> {
+        let provider = provider(vec![release("alpha", "1.0.0", &["bravo"])]);
+
+        let result = resolve(&provider, &deps(&["alpha"]), &ResolveOptions::default());
+
+        let Err(FactorioModApiError::ResolutionConflict(conflict)) = result else {
+            panic!("expected a conflict, got {result:?}");
+        };
+        assert_eq!(
+            conflict.to_string(),
+            "no releases of 'bravo' found\n  'alpha 1.0.0' depends on 'bravo'"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resolve_from_portal() -> Result<(), Box<dyn Error>> {
+        let (server, client) = setup()?;
+        let mock = server.mock(|when, then| {
+            when.method(GET).path("/api/mods/mymod/full");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(include_bytes!("tests/full.json"));
+        });
+
+        let resolution =
+            client.resolve_mods(&deps(&["mymod < 0.0.2"]), &ResolveOptions::default()).await?;
+
+        assert_eq!(versions(&resolution), ["mymod 0.0.1"]);
+        mock.assert_hits(1);
+        Ok(())
+    }
+}
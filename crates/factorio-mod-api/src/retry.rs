@@ -0,0 +1,72 @@
+//! Retry handling for failed mod portal requests.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::DateTime;
+use reqwest::{header::RETRY_AFTER, Response, StatusCode};
+
+/// Controls how often and after which delay failed requests are retried.
+///
+/// Requests are retried if they fail with a connection error or a timeout, if
+/// the mod portal responds with `429 Too Many Requests`, or with a server
+/// error. The delay between attempts doubles with each retry, starting at
+/// `initial_backoff` and limited to `max_backoff`. A `Retry-After` header sent
+/// by the server takes precedence, unless it asks for a longer delay than
+/// `max_backoff`, in which case the request fails right away.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Number of retries after the initial attempt.
+    pub max_retries: u32,
+
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+
+    /// Upper limit for the delay between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that doesn't retry failed requests at all.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy { max_retries: 0, ..Default::default() }
+    }
+
+    /// The delay before retry number `attempt` (starting at 0).
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Whether a response with this status code should be retried.
+pub(crate) fn is_retryable(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Whether a failed request should be retried.
+pub(crate) fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+/// Parses the `Retry-After` header of a response, which is either a number of
+/// seconds or an HTTP date.
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(u64::try_from(date.timestamp()).ok()?.saturating_sub(now)))
+}